@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use solana_sha256_hasher::hash;
 
 // 程序 ID（部署后的链上地址）
 declare_id!("5e44g7KZvJuhEPEuYX6S8tHWtb2FEyCg41HvDYwwV7z5");
@@ -6,6 +8,19 @@ declare_id!("5e44g7KZvJuhEPEuYX6S8tHWtb2FEyCg41HvDYwwV7z5");
 // PDA 派生种子：固定字符串 "data"
 const SEED: &[u8] = b"data";
 
+// 零拷贝大记录 PDA 的派生种子
+const LARGE_SEED: &[u8] = b"large";
+
+// 访问控制列表 PDA 的派生种子：["acl", 数据账户地址]
+const ACL_SEED: &[u8] = b"acl";
+
+// 程序写者登记 PDA 的派生种子：["program_writer", 数据账户地址, 调用方程序 ID]
+const PROGRAM_WRITER_SEED: &[u8] = b"program_writer";
+
+// 调用方程序用来签名的 PDA 种子：在调用方程序下派生 ["writer"]
+// 调用方通过 invoke_signed 用这个 PDA 签名调用 write_as_program
+pub const WRITER_AUTHORITY_SEED: &[u8] = b"writer";
+
 // ============================================
 // 指令定义：每个 pub fn 都是一条可调用的指令
 // ============================================
@@ -16,7 +31,8 @@ pub mod lesson_5_pxsol_ss_anchor {
     /// 指令 1：初始化 PDA 账户
     /// - 创建用户的数据存储账户
     /// - 记录所有者和 bump 值
-    pub fn init(ctx: Context<Init>) -> Result<()> {
+    /// - expires_at 为 None 表示永不过期，否则必须是未来的 unix 时间戳
+    pub fn init(ctx: Context<Init>, expires_at: Option<i64>) -> Result<()> {
         let account_user = &ctx.accounts.user;
         let account_user_pda = &mut ctx.accounts.user_pda;
         
@@ -24,43 +40,479 @@ pub mod lesson_5_pxsol_ss_anchor {
         account_user_pda.auth = account_user.key();
         // 记录 PDA 的 bump 值（Anchor 自动计算）
         account_user_pda.bump = ctx.bumps.user_pda;
+        // 记录支付租金的账户（reap 时租金退还给它）
+        account_user_pda.payer = account_user.key();
+        // 记录过期时间（0 表示永不过期）
+        account_user_pda.expires_at = match expires_at {
+            Some(expires_at) => check_expiry(expires_at)?,
+            None => 0,
+        };
+        // 新记录还没有 ACL 和登记的外部程序
+        account_user_pda.has_acl = false;
+        account_user_pda.program_writers = 0;
         // 初始化数据为空
         account_user_pda.data = Vec::new();
+        account_user_pda.refresh_hash();
+
+        emit!(DataInitialized {
+            user_pda: account_user_pda.key(),
+            auth: account_user_pda.auth,
+        });
         Ok(())
     }
 
     /// 指令 2：更新数据
     /// - 支持账户扩容/缩小
-    /// - 缩小时自动退还多余租金
+    /// - 缩小时自动退还多余租金（只有 payer 本人可以缩小）
     /// 
     /// 参数位置匹配：
     /// - 位置 1: data (Vec<u8>) ← 按顺序对应 #[instruction] 的声明
-    pub fn update(ctx: Context<Update>, data: Vec<u8>) -> Result<()> {
+    /// - 位置 2: expires_at：None 保持不变，Some(0) 取消过期，Some(ts) 设置新的过期时间
+    ///   （只有 auth 可以修改过期时间，ACL 写者不行）
+    pub fn update(ctx: Context<Update>, data: Vec<u8>, expires_at: Option<i64>) -> Result<()> {
         let account_user = &ctx.accounts.user;
         let account_user_pda = &mut ctx.accounts.user_pda;
 
-        // 更新数据内容
-        account_user_pda.data = data;
+        let (old_len, new_len) = write_data(account_user_pda, &account_user.key(), data, expires_at)?;
 
         // 账户缩小时，退还多余的租金
         // （扩容时 Anchor 自动补缴租金，但缩小时需要手动退款）
-        let account_user_pda_info = account_user_pda.to_account_info();
-        let rent_exemption = Rent::get()?.minimum_balance(account_user_pda_info.data_len());
-        let hold = **account_user_pda_info.lamports.borrow();
-        
-        if hold > rent_exemption {
-            let refund = hold.saturating_sub(rent_exemption);
-            **account_user_pda_info.lamports.borrow_mut() = rent_exemption;
-            **account_user.lamports.borrow_mut() = account_user.lamports().checked_add(refund).unwrap();
-        }
+        refund_excess_rent(&account_user_pda.to_account_info(), &account_user.to_account_info())?;
+
+        emit!(DataUpdated {
+            user_pda: account_user_pda.key(),
+            old_len,
+            new_len,
+        });
         Ok(())
     }
 
     /// 指令 3：关闭账户
     /// - 删除 PDA 账户
-    /// - 退还所有租金给用户
+    /// - 退还所有租金给支付租金的 payer（自己付租金时就是用户本人）
+    /// - 实际关闭由 #[account(close = payer)] 约束自动处理
+    /// - 存在 ACL 时必须一起传入并关闭（租金退还给 auth），否则同一钱包重新 init 后旧 ACL 会重新生效
+    /// - 已登记的外部程序同理，全部登记账户通过 remaining_accounts 传入
+    pub fn close<'info>(ctx: Context<'_, '_, 'info, 'info, Close<'info>>) -> Result<()> {
+        close_program_writers(
+            &mut ctx.accounts.user_pda,
+            ctx.remaining_accounts,
+            &ctx.accounts.user.to_account_info(),
+        )?;
+
+        // 指令结束后 Anchor 才真正关闭账户，这里账户余额就是将要退还的租金
+        let account_user_pda = &ctx.accounts.user_pda;
+        emit!(DataClosed {
+            user_pda: account_user_pda.key(),
+            auth: account_user_pda.auth,
+            refunded: account_user_pda.to_account_info().lamports(),
+        });
+        Ok(())
+    }
+
+    /// 指令 4：转移账户所有权
+    /// - 把 auth 改为新的钱包（用于密钥轮换）
+    /// - PDA 地址仍由最初的创建者钱包派生，不会改变
+    /// - 之后 update/close 只认新的 auth
+    pub fn transfer_auth(ctx: Context<TransferAuth>, new_auth: Pubkey) -> Result<()> {
+        let account_user_pda = &mut ctx.accounts.user_pda;
+        // 租金是用户自己付的，就跟着一起转给新钱包；赞助商付的租金仍归赞助商
+        if account_user_pda.payer == account_user_pda.auth {
+            account_user_pda.payer = new_auth;
+        }
+        account_user_pda.auth = new_auth;
+        Ok(())
+    }
+
+    /// 指令 5：局部修改数据
+    /// - 只覆盖 [offset, offset + bytes.len()) 这一段，不改变数据长度
+    /// - 直接读写账户原始字节，不做 Borsh 反序列化/序列化，大记录也很省计算单元
+    pub fn patch(ctx: Context<Patch>, offset: u32, bytes: Vec<u8>) -> Result<()> {
+        let account_user = &ctx.accounts.user;
+        let account_user_pda = &ctx.accounts.user_pda;
+        let mut raw = account_user_pda.try_borrow_mut_data()?;
+
+        // 手动完成 Account<Data> 会做的检查：类型 + 权限
+        require!(
+            raw.len() >= Data::DATA_OFFSET && raw[..8] == *Data::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        let auth = Pubkey::try_from(&raw[Data::AUTH_OFFSET..Data::AUTH_OFFSET + 32]).unwrap();
+        require_keys_eq!(auth, account_user.key(), PxsolError::Unauthorized);
+
+        // 边界检查：修改范围必须落在现有数据之内
+        let len_bytes: [u8; 4] = raw[Data::LEN_OFFSET..Data::DATA_OFFSET].try_into().unwrap();
+        let data_len = u32::from_le_bytes(len_bytes) as usize;
+        let start = offset as usize;
+        let end = start.checked_add(bytes.len()).ok_or(PxsolError::OutOfBounds)?;
+        require!(end <= data_len, PxsolError::OutOfBounds);
+
+        raw[Data::DATA_OFFSET + start..Data::DATA_OFFSET + end].copy_from_slice(&bytes);
+
+        // 重新计算整份数据的摘要（数据内容变了，摘要必须同步）
+        let digest = hash(&raw[Data::DATA_OFFSET..Data::DATA_OFFSET + data_len]).to_bytes();
+        raw[Data::HASH_OFFSET..Data::LEN_OFFSET].copy_from_slice(&digest);
+
+        emit!(DataPatched {
+            user_pda: account_user_pda.key(),
+            offset,
+            len: bytes.len() as u32,
+        });
+        Ok(())
+    }
+
+    /// 指令 6：只调整数据长度
+    /// - 扩容时新增部分填 0，缩小时截断并退还多余租金
+    /// - 不需要客户端重新上传整份数据
+    pub fn resize(ctx: Context<Resize>, new_len: u32) -> Result<()> {
+        let account_user = &ctx.accounts.user;
+        let account_user_pda = &mut ctx.accounts.user_pda;
+
+        let old_len = account_user_pda.data.len() as u32;
+        account_user_pda.data.resize(new_len as usize, 0);
+        account_user_pda.refresh_hash();
+
+        refund_excess_rent(&account_user_pda.to_account_info(), &account_user.to_account_info())?;
+
+        emit!(DataUpdated {
+            user_pda: account_user_pda.key(),
+            old_len,
+            new_len,
+        });
+        Ok(())
+    }
+
+    /// 指令 7：初始化零拷贝大记录账户
+    /// - 只分配头部，数据部分为空
+    /// - 之后通过 resize_large 逐步扩容（单条指令最多扩容 10 KB），最大可到 10 MB
+    pub fn init_large(ctx: Context<InitLarge>) -> Result<()> {
+        let mut header = ctx.accounts.large_pda.load_init()?;
+        header.auth = ctx.accounts.user.key();
+        header.bump = ctx.bumps.large_pda;
+
+        emit!(DataInitialized {
+            user_pda: ctx.accounts.large_pda.key(),
+            auth: header.auth,
+        });
+        Ok(())
+    }
+
+    /// 指令 8：调整大记录的数据长度
+    /// - 数据长度 = 账户大小 - 头部大小，不额外存储
+    /// - 扩容部分清零，缩小时退还多余租金
+    /// - 不用 realloc 约束：约束在指令前执行，事件就拿不到旧长度了
+    pub fn resize_large(ctx: Context<ResizeLarge>, new_len: u32) -> Result<()> {
+        let account_user = ctx.accounts.user.to_account_info();
+        let account_large_pda = ctx.accounts.large_pda.to_account_info();
+        let old_len = LargeData::data_len(&account_large_pda) as u32;
+        let new_space = LargeData::space_for(new_len as usize);
+
+        // 扩容：先补足免租金额（单条指令最多扩容 10 KB，超出时 resize 会报错）
+        resize_with_rent(&account_large_pda, &account_user, &ctx.accounts.system_program, new_space)?;
+
+        // 缩小：退还多余租金
+        refund_excess_rent(&account_large_pda, &account_user)?;
+
+        emit!(DataUpdated {
+            user_pda: account_large_pda.key(),
+            old_len,
+            new_len,
+        });
+        Ok(())
+    }
+
+    /// 指令 9：写入大记录的一段数据
+    /// - 数据部分是原始字节，直接按偏移写入，不经过 Borsh
+    pub fn write_large(ctx: Context<WriteLarge>, offset: u32, bytes: Vec<u8>) -> Result<()> {
+        let account_large_pda = ctx.accounts.large_pda.to_account_info();
+        let data_len = LargeData::data_len(&account_large_pda);
+        let start = offset as usize;
+        let end = start.checked_add(bytes.len()).ok_or(PxsolError::OutOfBounds)?;
+        require!(end <= data_len, PxsolError::OutOfBounds);
+
+        let mut raw = account_large_pda.try_borrow_mut_data()?;
+        raw[LargeData::TAIL_OFFSET + start..LargeData::TAIL_OFFSET + end].copy_from_slice(&bytes);
+
+        emit!(DataPatched {
+            user_pda: account_large_pda.key(),
+            offset,
+            len: bytes.len() as u32,
+        });
+        Ok(())
+    }
+
+    /// 指令 10：关闭大记录账户
     /// - 实际关闭由 #[account(close = user)] 约束自动处理
-    pub fn close(_ctx: Context<Close>) -> Result<()> {
+    pub fn close_large(ctx: Context<CloseLarge>) -> Result<()> {
+        let account_large_pda = ctx.accounts.large_pda.to_account_info();
+        emit!(DataClosed {
+            user_pda: account_large_pda.key(),
+            auth: ctx.accounts.user.key(),
+            refunded: account_large_pda.lamports(),
+        });
+        Ok(())
+    }
+
+    /// 指令 11：把 Borsh 格式的 Data 账户迁移为零拷贝大记录（最后一步）
+    /// - 迁移分三步：init_large 创建只有头部的 LargeData → migrate_chunk 分段扩容并复制 → migrate
+    ///   （单条指令最多扩容 10 KB，一次性创建同样大小的账户会超出限制）
+    /// - 这里确认数据已经完整复制，然后关闭旧账户，租金退还给 user
+    /// - LargeData 只有 auth 和 bump：
+    ///   - 没有 payer：赞助的记录不能迁移
+    ///   - 没有 expires_at：设置了过期时间的记录需要先取消过期，迁移后也不能 reap
+    ///   - 没有 hash：迁移后不能再用 verify 校验，需要链下自行比对
+    ///   - 不支持 ACL 和外部程序写入，需要先 close_acl / unregister_program_writer
+    pub fn migrate(ctx: Context<Migrate>) -> Result<()> {
+        let account_user_pda = &ctx.accounts.user_pda;
+        let account_large_pda = ctx.accounts.large_pda.to_account_info();
+        let raw = account_large_pda.try_borrow_data()?;
+        require!(
+            raw[LargeData::TAIL_OFFSET..] == account_user_pda.data[..],
+            PxsolError::MigrationIncomplete
+        );
+
+        emit!(DataClosed {
+            user_pda: account_user_pda.key(),
+            auth: account_user_pda.auth,
+            refunded: account_user_pda.to_account_info().lamports(),
+        });
+        Ok(())
+    }
+
+    /// 指令 12：为数据账户创建访问控制列表（ACL）
+    /// - 只有数据账户的 auth 可以创建
+    /// - readers 只是给链下读取方的提示（链上数据本来就是公开的）
+    /// - writers 里的成员可以调用 update；Owner 角色还可以修改 ACL
+    pub fn init_acl(ctx: Context<InitAcl>, readers: Vec<Pubkey>, writers: Vec<AclEntry>) -> Result<()> {
+        let acl = &mut ctx.accounts.acl;
+        acl.data = ctx.accounts.user_pda.key();
+        acl.bump = ctx.bumps.acl;
+        acl.readers = readers;
+        acl.writers = writers;
+        ctx.accounts.user_pda.has_acl = true;
+        Ok(())
+    }
+
+    /// 指令 13：整体替换 ACL 的成员列表
+    /// - 数据账户的 auth 或 Owner 角色成员可以调用
+    /// - 列表变短时退还多余租金；ACL 的租金由 auth 在 init_acl 时支付，所以只有 auth 可以缩小
+    pub fn set_acl(ctx: Context<SetAcl>, readers: Vec<Pubkey>, writers: Vec<AclEntry>) -> Result<()> {
+        let acl = &mut ctx.accounts.acl;
+        acl.readers = readers;
+        acl.writers = writers;
+        refund_excess_rent(&acl.to_account_info(), &ctx.accounts.user.to_account_info())?;
+        Ok(())
+    }
+
+    /// 指令 14：删除 ACL，数据账户恢复为单一所有者
+    /// - 只有数据账户的 auth 可以删除
+    pub fn close_acl(ctx: Context<CloseAcl>) -> Result<()> {
+        ctx.accounts.user_pda.has_acl = false;
+        Ok(())
+    }
+
+    /// 指令 15：回收过期账户
+    /// - 任何人都可以调用，不需要 auth 签名
+    /// - 账户必须设置了过期时间且已经过期
+    /// - 租金退还给最初支付租金的 payer，而不是调用者
+    /// - 存在 ACL 时必须一起传入并关闭，ACL 的租金退还给 auth
+    /// - 已登记的外部程序通过 remaining_accounts 传入，登记账户的租金同样退还给 auth
+    pub fn reap<'info>(ctx: Context<'_, '_, 'info, 'info, Reap<'info>>) -> Result<()> {
+        close_program_writers(
+            &mut ctx.accounts.user_pda,
+            ctx.remaining_accounts,
+            &ctx.accounts.auth.to_account_info(),
+        )?;
+
+        let account_user_pda = &ctx.accounts.user_pda;
+        emit!(DataClosed {
+            user_pda: account_user_pda.key(),
+            auth: account_user_pda.auth,
+            refunded: account_user_pda.to_account_info().lamports(),
+        });
+        Ok(())
+    }
+
+    /// 指令 16：由赞助商代付租金的初始化
+    /// - sponsor（例如中继服务）支付 PDA 租金，用户不需要持有 SOL
+    /// - auth 仍然记录为 user，payer 记录为 sponsor
+    pub fn init_sponsored(ctx: Context<InitSponsored>, expires_at: Option<i64>) -> Result<()> {
+        let account_user_pda = &mut ctx.accounts.user_pda;
+        account_user_pda.auth = ctx.accounts.user.key();
+        account_user_pda.bump = ctx.bumps.user_pda;
+        account_user_pda.payer = ctx.accounts.sponsor.key();
+        account_user_pda.expires_at = match expires_at {
+            Some(expires_at) => check_expiry(expires_at)?,
+            None => 0,
+        };
+        account_user_pda.has_acl = false;
+        account_user_pda.program_writers = 0;
+        account_user_pda.data = Vec::new();
+        account_user_pda.refresh_hash();
+
+        emit!(DataInitialized {
+            user_pda: account_user_pda.key(),
+            auth: account_user_pda.auth,
+        });
+        Ok(())
+    }
+
+    /// 指令 17：由赞助商代付租金的更新
+    /// - 扩容租金由 sponsor 补缴，缩小退款也回到 sponsor
+    /// - sponsor 必须是记录的 payer；user 仍需是 auth 或 ACL 写者
+    pub fn update_sponsored(ctx: Context<UpdateSponsored>, data: Vec<u8>, expires_at: Option<i64>) -> Result<()> {
+        let account_user_pda = &mut ctx.accounts.user_pda;
+
+        let (old_len, new_len) = write_data(account_user_pda, &ctx.accounts.user.key(), data, expires_at)?;
+        refund_excess_rent(&account_user_pda.to_account_info(), &ctx.accounts.sponsor.to_account_info())?;
+
+        emit!(DataUpdated {
+            user_pda: account_user_pda.key(),
+            old_len,
+            new_len,
+        });
+        Ok(())
+    }
+
+    /// 指令 18：登记一个可以写入的外部程序
+    /// - 只有 auth 可以登记
+    /// - 记录该程序的写者 PDA（["writer"] 在 program_id 下派生），只有该程序能用它签名
+    pub fn register_program_writer(ctx: Context<RegisterProgramWriter>, program_id: Pubkey) -> Result<()> {
+        let program_writer = &mut ctx.accounts.program_writer;
+        program_writer.data = ctx.accounts.user_pda.key();
+        program_writer.program_id = program_id;
+        program_writer.authority = Pubkey::find_program_address(&[WRITER_AUTHORITY_SEED], &program_id).0;
+        program_writer.bump = ctx.bumps.program_writer;
+
+        let account_user_pda = &mut ctx.accounts.user_pda;
+        account_user_pda.program_writers = account_user_pda.program_writers.checked_add(1).unwrap();
+        Ok(())
+    }
+
+    /// 指令 19：取消外部程序的写权限
+    /// - 只有 auth 可以取消，登记账户的租金退还给 auth
+    pub fn unregister_program_writer(ctx: Context<UnregisterProgramWriter>) -> Result<()> {
+        let account_user_pda = &mut ctx.accounts.user_pda;
+        account_user_pda.program_writers = account_user_pda.program_writers.checked_sub(1).unwrap();
+        Ok(())
+    }
+
+    /// 指令 20：由已登记的外部程序通过 CPI 写入数据
+    /// - 调用方程序用自己的写者 PDA 签名（invoke_signed）
+    /// - payer 补缴扩容租金；只有记录的 payer 才能缩小
+    /// - 不能修改过期时间
+    pub fn write_as_program(ctx: Context<WriteAsProgram>, data: Vec<u8>) -> Result<()> {
+        let account_user_pda = &mut ctx.accounts.user_pda;
+
+        let old_len = account_user_pda.data.len() as u32;
+        let new_len = data.len() as u32;
+        account_user_pda.data = data;
+        account_user_pda.refresh_hash();
+        refund_excess_rent(&account_user_pda.to_account_info(), &ctx.accounts.payer.to_account_info())?;
+
+        emit!(DataUpdated {
+            user_pda: account_user_pda.key(),
+            old_len,
+            new_len,
+        });
+        Ok(())
+    }
+
+    /// 指令 21：校验链下数据与链上一致
+    /// - 比较链上记录的 SHA-256 摘要，不一致时交易失败
+    /// - 只读取头部，不需要下载整份数据
+    pub fn verify(ctx: Context<Verify>, expected_hash: [u8; 32]) -> Result<()> {
+        let raw = ctx.accounts.user_pda.try_borrow_data()?;
+        require!(
+            raw.len() >= Data::DATA_OFFSET && raw[..8] == *Data::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        require!(raw[Data::HASH_OFFSET..Data::LEN_OFFSET] == expected_hash, PxsolError::HashMismatch);
+        Ok(())
+    }
+
+    /// 指令 22：把 Data 的一段数据复制到 LargeData 的相同偏移
+    /// - 需要时先把 LargeData 扩容到 offset + len（单条指令最多扩容 10 KB，所以 len 每次不超过 10240）
+    /// - 直接读取 Data 的原始字节，不做 Borsh 反序列化，再大的记录也能分段复制
+    pub fn migrate_chunk(ctx: Context<MigrateChunk>, offset: u32, len: u32) -> Result<()> {
+        let account_user = ctx.accounts.user.to_account_info();
+        let account_large_pda = ctx.accounts.large_pda.to_account_info();
+        let raw = ctx.accounts.user_pda.try_borrow_data()?;
+
+        // 手动完成 Account<Data> 会做的检查：类型 + 权限
+        require!(
+            raw.len() >= Data::DATA_OFFSET && raw[..8] == *Data::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        let auth = Pubkey::try_from(&raw[Data::AUTH_OFFSET..Data::AUTH_OFFSET + 32]).unwrap();
+        require_keys_eq!(auth, account_user.key(), PxsolError::Unauthorized);
+
+        // 边界检查：复制范围必须落在现有数据之内
+        let len_bytes: [u8; 4] = raw[Data::LEN_OFFSET..Data::DATA_OFFSET].try_into().unwrap();
+        let data_len = u32::from_le_bytes(len_bytes) as usize;
+        let start = offset as usize;
+        let end = start.checked_add(len as usize).ok_or(PxsolError::OutOfBounds)?;
+        require!(end <= data_len, PxsolError::OutOfBounds);
+
+        // 只扩容不缩小，分段可以按任意顺序复制
+        if LargeData::data_len(&account_large_pda) < end {
+            let new_space = LargeData::space_for(end);
+            resize_with_rent(&account_large_pda, &account_user, &ctx.accounts.system_program, new_space)?;
+        }
+
+        let mut tail = account_large_pda.try_borrow_mut_data()?;
+        tail[LargeData::TAIL_OFFSET + start..LargeData::TAIL_OFFSET + end]
+            .copy_from_slice(&raw[Data::DATA_OFFSET + start..Data::DATA_OFFSET + end]);
+
+        emit!(DataPatched {
+            user_pda: account_large_pda.key(),
+            offset,
+            len,
+        });
+        Ok(())
+    }
+
+    /// 指令 23：把旧布局（v0：auth + bump + data）的记录升级为当前布局
+    /// - 加入 payer / expires_at 等字段后，旧记录不能再按 Account<Data> 反序列化，update / close / reap 都会失败，租金被锁住
+    /// - 直接读取原始字节：payer 记为 auth，不过期，没有 ACL 和外部程序，并重新计算摘要
+    /// - 账户变大所需的租金由 auth 补缴；升级后即可照常 update / close
+    pub fn upgrade_legacy(ctx: Context<UpgradeLegacy>) -> Result<()> {
+        let account_user = ctx.accounts.user.to_account_info();
+        let account_user_pda = ctx.accounts.user_pda.to_account_info();
+
+        let mut record = {
+            let raw = account_user_pda.try_borrow_data()?;
+            require!(
+                raw.len() >= Data::LEGACY_DATA_OFFSET && raw[..8] == *Data::DISCRIMINATOR,
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            // 两种布局的长度前缀位置不同：只符合旧布局、不符合当前布局的才是旧记录
+            let len_at = |offset: usize| {
+                raw.get(offset - 4..offset).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+            };
+            let is_current = len_at(Data::DATA_OFFSET).is_some_and(|len| raw.len() == Data::DATA_OFFSET + len);
+            let is_legacy = len_at(Data::LEGACY_DATA_OFFSET) == Some(raw.len() - Data::LEGACY_DATA_OFFSET);
+            require!(is_legacy && !is_current, PxsolError::NotLegacy);
+
+            let auth = Pubkey::try_from(&raw[Data::AUTH_OFFSET..Data::AUTH_OFFSET + 32]).unwrap();
+            Data {
+                auth,
+                bump: raw[Data::AUTH_OFFSET + 32],
+                payer: auth,
+                expires_at: 0,
+                has_acl: false,
+                program_writers: 0,
+                hash: [0; 32],
+                data: raw[Data::LEGACY_DATA_OFFSET..].to_vec(),
+            }
+        };
+        require_keys_eq!(record.auth, account_user.key(), PxsolError::Unauthorized);
+        record.refresh_hash();
+
+        // 新布局多出 payer 等字段，先扩容并补足租金，再整体写回
+        let new_space = Data::space_for(record.data.len());
+        resize_with_rent(&account_user_pda, &account_user, &ctx.accounts.system_program, new_space)?;
+        record.try_serialize(&mut &mut account_user_pda.try_borrow_mut_data()?[..])?;
         Ok(())
     }
 }
@@ -73,19 +525,212 @@ pub mod lesson_5_pxsol_ss_anchor {
 /// #[account] 宏会自动添加 8 字节的 discriminator 用于类型识别
 #[account]
 pub struct Data {
-    pub auth: Pubkey,  // 账户所有者（32 字节）
-    pub bump: u8,      // PDA 的 bump 值（1 字节）
-    pub data: Vec<u8>  // 用户存储的数据（4字节长度 + 实际数据）
+    pub auth: Pubkey,     // 账户所有者（32 字节）
+    pub bump: u8,         // PDA 的 bump 值（1 字节）
+    pub payer: Pubkey,    // 支付租金的账户，reap 时退还给它（32 字节）
+    pub expires_at: i64,  // 过期的 unix 时间戳，0 表示永不过期（8 字节）
+    pub has_acl: bool,    // 是否存在 ACL，close / reap 时必须一起关闭（1 字节）
+    pub program_writers: u32,  // 已登记的外部程序数量，close / reap 时必须全部关闭（4 字节）
+    pub hash: [u8; 32],   // data 的 SHA-256 摘要，每次写入后更新（32 字节）
+    pub data: Vec<u8>     // 用户存储的数据（4字节长度 + 实际数据），必须放在最后
 }
 
 impl Data {
     /// 计算账户所需空间
-    /// 结构：discriminator(8) + auth(32) + bump(1) + vec_len(4) + data
+    /// 结构：discriminator(8) + auth(32) + bump(1) + payer(32) + expires_at(8) + has_acl(1) + program_writers(4) + hash(32) + vec_len(4) + data
     pub fn space_for(data_len: usize) -> usize {
-        8 + 32 + 1 + 4 + data_len
+        Self::DATA_OFFSET + data_len
+    }
+
+    /// 原始字节中各字段的偏移量（供 patch / verify 直接读写使用）
+    pub const AUTH_OFFSET: usize = 8;
+    pub const HASH_OFFSET: usize = 8 + 32 + 1 + 32 + 8 + 1 + 4;
+    pub const LEN_OFFSET: usize = Self::HASH_OFFSET + 32;
+    pub const DATA_OFFSET: usize = Self::LEN_OFFSET + 4;
+    /// 旧布局（v0）：discriminator(8) + auth(32) + bump(1) + vec_len(4) + data，供 upgrade_legacy 使用
+    pub const LEGACY_DATA_OFFSET: usize = 8 + 32 + 1 + 4;
+
+    /// 用 sha256 系统调用重新计算 data 的摘要
+    pub fn refresh_hash(&mut self) {
+        self.hash = hash(&self.data).to_bytes();
+    }
+
+    /// 是否已经过期（未设置过期时间的账户永不过期）
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+}
+
+/// 零拷贝大记录的头部
+/// - #[account(zero_copy)] 直接把账户字节映射成结构体，不做反序列化，也不占堆内存
+/// - 头部之后紧跟原始数据字节（tail），长度 = 账户大小 - TAIL_OFFSET
+/// - 账户最大 10 MB，适合存放 Borsh 格式放不下或算力不够的大记录
+#[account(zero_copy)]
+pub struct LargeData {
+    pub auth: Pubkey,  // 账户所有者（32 字节）
+    pub bump: u8,      // PDA 的 bump 值（1 字节）
+}
+
+impl LargeData {
+    /// 数据部分在账户中的起始偏移：discriminator(8) + 头部
+    pub const TAIL_OFFSET: usize = 8 + std::mem::size_of::<LargeData>();
+
+    /// 计算账户所需空间：头部 + data_len
+    pub fn space_for(data_len: usize) -> usize {
+        Self::TAIL_OFFSET + data_len
+    }
+
+    /// 当前数据长度
+    pub fn data_len(info: &AccountInfo) -> usize {
+        info.data_len().saturating_sub(Self::TAIL_OFFSET)
+    }
+}
+
+/// ACL 成员的角色
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Owner,   // 可以编辑数据，也可以修改 ACL
+    Editor,  // 只能编辑数据
+}
+
+/// ACL 中的一条写权限记录
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct AclEntry {
+    pub key: Pubkey,  // 成员公钥（32 字节）
+    pub role: Role,   // 角色（1 字节）
+}
+
+/// 数据账户的访问控制列表
+/// - 每个数据账户最多一个 ACL，PDA 种子：["acl", 数据账户地址]
+/// - 数据账户的 auth 始终拥有全部权限，不需要写进 writers
+/// - close 仍然只允许 auth 调用
+#[account]
+pub struct Acl {
+    pub data: Pubkey,            // 对应的数据账户（32 字节）
+    pub bump: u8,                // PDA 的 bump 值（1 字节）
+    pub readers: Vec<Pubkey>,    // 读者列表，仅供链下参考（4 字节长度 + 32 * n）
+    pub writers: Vec<AclEntry>,  // 写者列表（4 字节长度 + 33 * n）
+}
+
+impl Acl {
+    /// 计算账户所需空间
+    /// 结构：discriminator(8) + data(32) + bump(1) + readers(4 + 32n) + writers(4 + 33n)
+    pub fn space_for(readers: usize, writers: usize) -> usize {
+        8 + 32 + 1 + 4 + 32 * readers + 4 + 33 * writers
+    }
+
+    /// 是否可以编辑数据（Owner 和 Editor 都可以）
+    pub fn can_write(&self, key: &Pubkey) -> bool {
+        self.writers.iter().any(|e| e.key == *key)
+    }
+
+    /// 是否可以修改 ACL（只有 Owner）
+    pub fn is_owner(&self, key: &Pubkey) -> bool {
+        self.writers.iter().any(|e| e.key == *key && e.role == Role::Owner)
     }
 }
 
+/// 外部程序的写权限登记
+/// - PDA 种子：["program_writer", 数据账户地址, 程序 ID]
+/// - authority 是该程序下 ["writer"] 派生的 PDA，只有该程序能用它签名
+#[account]
+pub struct ProgramWriter {
+    pub data: Pubkey,        // 对应的数据账户（32 字节）
+    pub program_id: Pubkey,  // 被授权的程序（32 字节）
+    pub authority: Pubkey,   // 该程序的写者 PDA（32 字节）
+    pub bump: u8,            // PDA 的 bump 值（1 字节）
+}
+
+impl ProgramWriter {
+    /// 结构：discriminator(8) + data(32) + program_id(32) + authority(32) + bump(1)
+    pub const SPACE: usize = 8 + 32 + 32 + 32 + 1;
+}
+
+// ============================================
+// 辅助函数
+// ============================================
+
+/// update / update_sponsored 共用的写入逻辑，返回 (旧长度, 新长度)
+/// - expires_at：None 保持不变，Some(0) 取消过期，Some(ts) 设置新的过期时间（只有 auth 可以修改）
+fn write_data(account: &mut Data, user: &Pubkey, data: Vec<u8>, expires_at: Option<i64>) -> Result<(u32, u32)> {
+    if let Some(expires_at) = expires_at {
+        require_keys_eq!(account.auth, *user, PxsolError::Unauthorized);
+        account.expires_at = match expires_at {
+            0 => 0,
+            expires_at => check_expiry(expires_at)?,
+        };
+    }
+
+    // 此时 data 字段仍是反序列化出的旧值，可以拿到旧长度
+    let old_len = account.data.len() as u32;
+    let new_len = data.len() as u32;
+    account.data = data;
+    account.refresh_hash();
+    Ok((old_len, new_len))
+}
+
+/// 校验过期时间必须晚于当前 Clock 时间，返回校验后的值
+fn check_expiry(expires_at: i64) -> Result<i64> {
+    require!(expires_at > Clock::get()?.unix_timestamp, PxsolError::InvalidExpiry);
+    Ok(expires_at)
+}
+
+/// 账户余额超过免租金额时，把多余的 lamports 退还给 user
+/// （扩容时 Anchor 的 realloc 会自动补缴，缩小时需要手动退款）
+fn refund_excess_rent<'info>(pda: &AccountInfo<'info>, user: &AccountInfo<'info>) -> Result<()> {
+    let rent_exemption = Rent::get()?.minimum_balance(pda.data_len());
+    let hold = pda.lamports();
+
+    if hold > rent_exemption {
+        let refund = hold.saturating_sub(rent_exemption);
+        **pda.lamports.borrow_mut() = rent_exemption;
+        **user.lamports.borrow_mut() = user.lamports().checked_add(refund).unwrap();
+    }
+    Ok(())
+}
+
+/// 调整账户大小，扩容时先由 payer 补足免租金额（缩小时的退款由调用方处理）
+fn resize_with_rent<'info>(
+    pda: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    new_space: usize,
+) -> Result<()> {
+    let rent_exemption = Rent::get()?.minimum_balance(new_space);
+    if rent_exemption > pda.lamports() {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: pda.clone(),
+                },
+            ),
+            rent_exemption - pda.lamports(),
+        )?;
+    }
+    pda.resize(new_space)?;
+    Ok(())
+}
+
+/// close / reap 共用：关闭 remaining_accounts 中属于该数据账户的全部登记账户，租金退还给 dest
+/// - 关闭后 program_writers 必须归零，否则同一钱包重新 init 后旧的登记会重新生效
+fn close_program_writers<'info>(
+    account: &mut Account<'info, Data>,
+    writers: &'info [AccountInfo<'info>],
+    dest: &AccountInfo<'info>,
+) -> Result<()> {
+    for info in writers {
+        // Account::try_from 校验 owner 和 discriminator；登记账户只能由本程序按种子创建
+        let program_writer = Account::<ProgramWriter>::try_from(info)?;
+        require_keys_eq!(program_writer.data, account.key(), PxsolError::Unauthorized);
+        program_writer.close(dest.clone())?;
+        account.program_writers = account.program_writers.checked_sub(1).unwrap();
+    }
+    require!(account.program_writers == 0, PxsolError::ProgramWriterStillOpen);
+    Ok(())
+}
+
 // ============================================
 // 账户约束定义：init 指令
 // ============================================
@@ -142,17 +787,29 @@ pub struct Update<'info> {
     /// 要更新的 PDA 账户
     #[account(
         mut,                                     // 可写（需要修改数据）
-        seeds = [SEED, user.key().as_ref()],    // 验证 PDA 派生规则
-        bump = user_pda.bump,                    // 使用存储的 bump 值
+        // 不再用 seeds 校验：所有权转移后 user 不一定是派生 PDA 的钱包
+        // Account<Data> 已保证账户属于本程序且类型正确，权限由 auth 决定
         realloc = Data::space_for(new_data.len()),  // 动态调整空间（使用声明的参数）
         realloc::payer = user,                   // 扩容时由 user 补缴租金
         realloc::zero = false,                   // 不清零新空间（节省计算单元）
-        constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized,  // 权限检查
+        // 权限检查：auth 本人，或 ACL 中的写者
+        constraint = user_pda.auth == user.key()
+            || acl.as_ref().is_some_and(|acl| acl.can_write(&user.key())) @ PxsolError::Unauthorized,
+        // 缩小会把租金退给 user，所以只有 payer 本人可以缩小（赞助的记录请用 update_sponsored）
+        constraint = user_pda.payer == user.key()
+            || new_data.len() >= user_pda.data.len() @ PxsolError::WrongPayer,
     )]
     pub user_pda: Account<'info, Data>,
     
     /// 系统程序（用于重新分配空间和转账）
     pub system_program: Program<'info, System>,
+
+    /// 可选：数据账户的 ACL（由非 auth 的写者调用时传入）
+    #[account(
+        seeds = [ACL_SEED, user_pda.key().as_ref()],  // 必须是这个数据账户自己的 ACL
+        bump = acl.bump,
+    )]
+    pub acl: Option<Account<'info, Acl>>,
 }
 
 // ============================================
@@ -160,21 +817,564 @@ pub struct Update<'info> {
 // ============================================
 
 /// Close 指令的账户列表
+/// - remaining_accounts：该记录全部的 ProgramWriter 登记账户（可写），随记录一起关闭
 #[derive(Accounts)]
 pub struct Close<'info> {
-    /// 调用者账户（接收退还的租金）
+    /// 调用者账户（必须是 auth）
     #[account(mut)]
     pub user: Signer<'info>,
     
     /// 要关闭的 PDA 账户
     #[account(
         mut,                                     // 可写
-        seeds = [SEED, user.key().as_ref()],    // 验证 PDA
-        bump = user_pda.bump,                    // 验证 bump
-        close = user,                            // 关闭账户，租金退还给 user
+        close = payer,                           // 关闭账户，租金退还给支付租金的 payer
+        has_one = payer @ PxsolError::WrongPayer,  // payer 必须是记录的租金支付者
         constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized,  // 权限检查
+        constraint = !user_pda.has_acl || acl.is_some() @ PxsolError::AclStillOpen,  // 不能留下 ACL
+    )]
+    pub user_pda: Account<'info, Data>,
+
+    /// 接收租金的账户（自己付租金时就是 user 本人）
+    /// CHECK: 只接收 lamports，地址由 has_one 校验
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+
+    /// 可选：数据账户的 ACL（存在时必须传入，与数据账户一起关闭）
+    #[account(
+        mut,
+        seeds = [ACL_SEED, user_pda.key().as_ref()],
+        bump = acl.bump,
+        close = user,
+    )]
+    pub acl: Option<Account<'info, Acl>>,
+}
+
+// ============================================
+// 账户约束定义：transfer_auth 指令
+// ============================================
+
+/// TransferAuth 指令的账户列表
+#[derive(Accounts)]
+pub struct TransferAuth<'info> {
+    /// 当前所有者
+    pub user: Signer<'info>,
+
+    /// 要转移所有权的 PDA 账户
+    #[account(
+        mut,                                     // 可写（需要修改 auth）
+        constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized,  // 权限检查
+    )]
+    pub user_pda: Account<'info, Data>,
+}
+
+// ============================================
+// 账户约束定义：patch 指令
+// ============================================
+
+/// Patch 指令的账户列表
+#[derive(Accounts)]
+pub struct Patch<'info> {
+    /// 调用者账户（必须是当前 auth）
+    pub user: Signer<'info>,
+
+    /// 要修改的 PDA 账户
+    /// CHECK: 故意不用 Account<Data>，避免整份反序列化；
+    /// owner 由约束保证，discriminator 和 auth 在指令内手动校验
+    #[account(mut, owner = crate::ID)]
+    pub user_pda: UncheckedAccount<'info>,
+}
+
+// ============================================
+// 账户约束定义：resize 指令
+// ============================================
+
+/// Resize 指令的账户列表
+#[derive(Accounts)]
+#[instruction(new_len: u32)]
+pub struct Resize<'info> {
+    /// 调用者账户（扩容时补缴租金，缩小时接收退款）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 要调整长度的 PDA 账户
+    #[account(
+        mut,
+        realloc = Data::space_for(new_len as usize),
+        realloc::payer = user,
+        realloc::zero = true,                    // 新增部分清零，和 data.resize(.., 0) 保持一致
+        constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized,
+        // 缩小会把租金退给 user，所以只有 payer 本人可以缩小
+        constraint = user_pda.payer == user.key()
+            || new_len as usize >= user_pda.data.len() @ PxsolError::WrongPayer,
+    )]
+    pub user_pda: Account<'info, Data>,
+
+    /// 系统程序（用于补缴租金）
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================
+// 账户约束定义：零拷贝大记录
+// ============================================
+
+/// InitLarge 指令的账户列表
+#[derive(Accounts)]
+pub struct InitLarge<'info> {
+    /// 调用者账户（支付租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 要创建的大记录 PDA（初始只有头部）
+    #[account(
+        init,
+        payer = user,
+        seeds = [LARGE_SEED, user.key().as_ref()],  // PDA 种子：["large", 用户公钥]
+        bump,
+        space = LargeData::space_for(0)
+    )]
+    pub large_pda: AccountLoader<'info, LargeData>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// ResizeLarge 指令的账户列表
+#[derive(Accounts)]
+pub struct ResizeLarge<'info> {
+    /// 调用者账户（扩容时补缴租金，缩小时接收退款）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 要调整长度的大记录 PDA
+    #[account(
+        mut,
+        constraint = large_pda.load()?.auth == user.key() @ PxsolError::Unauthorized,
+    )]
+    pub large_pda: AccountLoader<'info, LargeData>,
+
+    /// 系统程序（用于补缴租金）
+    pub system_program: Program<'info, System>,
+}
+
+/// WriteLarge 指令的账户列表
+#[derive(Accounts)]
+pub struct WriteLarge<'info> {
+    /// 调用者账户（必须是当前 auth）
+    pub user: Signer<'info>,
+
+    /// 要写入的大记录 PDA
+    #[account(
+        mut,
+        constraint = large_pda.load()?.auth == user.key() @ PxsolError::Unauthorized,
+    )]
+    pub large_pda: AccountLoader<'info, LargeData>,
+}
+
+/// CloseLarge 指令的账户列表
+#[derive(Accounts)]
+pub struct CloseLarge<'info> {
+    /// 调用者账户（接收退还的租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 要关闭的大记录 PDA
+    #[account(
+        mut,
+        close = user,
+        constraint = large_pda.load()?.auth == user.key() @ PxsolError::Unauthorized,
+    )]
+    pub large_pda: AccountLoader<'info, LargeData>,
+}
+
+/// Migrate 指令的账户列表
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// 调用者账户（接收旧账户租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 旧的 Borsh 格式账户（迁移完成后关闭）
+    #[account(
+        mut,
+        close = user,
+        constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized,
+        constraint = user_pda.payer == user.key() @ PxsolError::WrongPayer,  // 赞助的记录不能迁移，否则租金会退错人
+        constraint = user_pda.expires_at == 0 @ PxsolError::ExpiringRecord,  // LargeData 没有过期时间
+        constraint = !user_pda.has_acl @ PxsolError::AclStillOpen,  // LargeData 不支持 ACL，需先 close_acl
+        constraint = user_pda.program_writers == 0 @ PxsolError::ProgramWriterStillOpen,  // 也不支持外部程序写入
+    )]
+    pub user_pda: Account<'info, Data>,
+
+    /// 已经由 migrate_chunk 复制好数据的大记录 PDA
+    #[account(
+        seeds = [LARGE_SEED, user.key().as_ref()],
+        bump = large_pda.load()?.bump,
+        constraint = large_pda.load()?.auth == user.key() @ PxsolError::Unauthorized,
+        constraint = LargeData::data_len(&large_pda.to_account_info()) == user_pda.data.len()
+            @ PxsolError::MigrationIncomplete,
+    )]
+    pub large_pda: AccountLoader<'info, LargeData>,
+}
+
+/// MigrateChunk 指令的账户列表
+#[derive(Accounts)]
+pub struct MigrateChunk<'info> {
+    /// 调用者账户（补缴扩容租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 要迁移的 Data 账户
+    /// CHECK: 故意不用 Account<Data>，避免整份反序列化；
+    /// owner 由约束保证，discriminator 和 auth 在指令内手动校验
+    #[account(owner = crate::ID)]
+    pub user_pda: UncheckedAccount<'info>,
+
+    /// 目标大记录 PDA（先用 init_large 创建）
+    #[account(
+        mut,
+        seeds = [LARGE_SEED, user.key().as_ref()],
+        bump = large_pda.load()?.bump,
+        constraint = large_pda.load()?.auth == user.key() @ PxsolError::Unauthorized,
+    )]
+    pub large_pda: AccountLoader<'info, LargeData>,
+
+    /// 系统程序（用于补缴租金）
+    pub system_program: Program<'info, System>,
+}
+
+/// UpgradeLegacy 指令的账户列表
+#[derive(Accounts)]
+pub struct UpgradeLegacy<'info> {
+    /// 记录的所有者（补缴扩容租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 旧布局的数据账户
+    /// CHECK: 旧布局无法按 Account<Data> 反序列化；
+    /// owner 由约束保证，discriminator、布局和 auth 在指令内手动校验
+    #[account(mut, owner = crate::ID)]
+    pub user_pda: UncheckedAccount<'info>,
+
+    /// 系统程序（用于补缴租金）
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================
+// 账户约束定义：ACL
+// ============================================
+
+/// InitAcl 指令的账户列表
+#[derive(Accounts)]
+#[instruction(readers: Vec<Pubkey>, writers: Vec<AclEntry>)]
+pub struct InitAcl<'info> {
+    /// 数据账户的所有者（支付 ACL 租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 要共享的数据账户（记录 has_acl）
+    #[account(mut, constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized)]
+    pub user_pda: Account<'info, Data>,
+
+    /// 要创建的 ACL
+    #[account(
+        init,
+        payer = user,
+        seeds = [ACL_SEED, user_pda.key().as_ref()],
+        bump,
+        space = Acl::space_for(readers.len(), writers.len())
     )]
+    pub acl: Account<'info, Acl>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// SetAcl 指令的账户列表
+#[derive(Accounts)]
+#[instruction(readers: Vec<Pubkey>, writers: Vec<AclEntry>)]
+pub struct SetAcl<'info> {
+    /// auth 或 Owner 角色成员（扩容时补缴租金；只有 auth 可以缩小并接收退款）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// ACL 对应的数据账户
     pub user_pda: Account<'info, Data>,
+
+    /// 要修改的 ACL
+    #[account(
+        mut,
+        seeds = [ACL_SEED, user_pda.key().as_ref()],
+        bump = acl.bump,
+        realloc = Acl::space_for(readers.len(), writers.len()),
+        realloc::payer = user,
+        realloc::zero = false,
+        constraint = user_pda.auth == user.key() || acl.is_owner(&user.key()) @ PxsolError::Unauthorized,
+        // 缩小会把租金退给 user，Owner 缩小就能拿走 auth 付的租金（acl 的字段此时仍是旧值）
+        constraint = user_pda.auth == user.key()
+            || Acl::space_for(readers.len(), writers.len()) >= Acl::space_for(acl.readers.len(), acl.writers.len())
+            @ PxsolError::WrongPayer,
+    )]
+    pub acl: Account<'info, Acl>,
+
+    /// 系统程序（用于重新分配空间和转账）
+    pub system_program: Program<'info, System>,
+}
+
+/// CloseAcl 指令的账户列表
+#[derive(Accounts)]
+pub struct CloseAcl<'info> {
+    /// 数据账户的所有者（接收退还的租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// ACL 对应的数据账户（清除 has_acl）
+    #[account(mut, constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized)]
+    pub user_pda: Account<'info, Data>,
+
+    /// 要删除的 ACL
+    #[account(
+        mut,
+        seeds = [ACL_SEED, user_pda.key().as_ref()],
+        bump = acl.bump,
+        close = user,
+    )]
+    pub acl: Account<'info, Acl>,
+}
+
+// ============================================
+// 账户约束定义：reap 指令
+// ============================================
+
+/// Reap 指令的账户列表
+/// - 没有 Signer：任何人都可以提交（只需支付交易手续费）
+/// - remaining_accounts：该记录全部的 ProgramWriter 登记账户（可写），随记录一起关闭
+#[derive(Accounts)]
+pub struct Reap<'info> {
+    /// 要回收的过期 PDA 账户
+    #[account(
+        mut,
+        close = payer,                           // 租金退还给最初的 payer
+        constraint = user_pda.is_expired(Clock::get()?.unix_timestamp) @ PxsolError::NotExpired,
+        constraint = !user_pda.has_acl || acl.is_some() @ PxsolError::AclStillOpen,
+    )]
+    pub user_pda: Account<'info, Data>,
+
+    /// 接收租金的账户
+    /// CHECK: 只接收 lamports，地址必须等于 user_pda.payer
+    #[account(mut, address = user_pda.payer)]
+    pub payer: UncheckedAccount<'info>,
+
+    /// 接收 ACL 租金的账户
+    /// CHECK: 只接收 lamports，地址必须等于 user_pda.auth
+    #[account(mut, address = user_pda.auth)]
+    pub auth: UncheckedAccount<'info>,
+
+    /// 可选：数据账户的 ACL（存在时必须传入，与数据账户一起关闭）
+    #[account(
+        mut,
+        seeds = [ACL_SEED, user_pda.key().as_ref()],
+        bump = acl.bump,
+        close = auth,
+    )]
+    pub acl: Option<Account<'info, Acl>>,
+}
+
+// ============================================
+// 账户约束定义：赞助商代付租金
+// ============================================
+
+/// InitSponsored 指令的账户列表
+#[derive(Accounts)]
+pub struct InitSponsored<'info> {
+    /// 赞助商（支付租金和交易费）
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    /// 终端用户（记录为 auth，签名表示同意创建，不需要持有 SOL）
+    pub user: Signer<'info>,
+
+    /// 要创建的 PDA 账户，地址仍由 user 派生
+    #[account(
+        init,
+        payer = sponsor,
+        seeds = [SEED, user.key().as_ref()],
+        bump,
+        space = Data::space_for(0)
+    )]
+    pub user_pda: Account<'info, Data>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// UpdateSponsored 指令的账户列表
+#[derive(Accounts)]
+#[instruction(new_data: Vec<u8>)]
+pub struct UpdateSponsored<'info> {
+    /// 赞助商（补缴或接收租金），必须是记录的 payer
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    /// 终端用户（auth 或 ACL 写者）
+    pub user: Signer<'info>,
+
+    /// 要更新的 PDA 账户
+    #[account(
+        mut,
+        realloc = Data::space_for(new_data.len()),
+        realloc::payer = sponsor,
+        realloc::zero = false,
+        constraint = user_pda.payer == sponsor.key() @ PxsolError::WrongPayer,
+        constraint = user_pda.auth == user.key()
+            || acl.as_ref().is_some_and(|acl| acl.can_write(&user.key())) @ PxsolError::Unauthorized,
+    )]
+    pub user_pda: Account<'info, Data>,
+
+    /// 系统程序（用于重新分配空间和转账）
+    pub system_program: Program<'info, System>,
+
+    /// 可选：数据账户的 ACL（由非 auth 的写者调用时传入）
+    #[account(
+        seeds = [ACL_SEED, user_pda.key().as_ref()],
+        bump = acl.bump,
+    )]
+    pub acl: Option<Account<'info, Acl>>,
+}
+
+// ============================================
+// 账户约束定义：外部程序写入
+// ============================================
+
+/// RegisterProgramWriter 指令的账户列表
+#[derive(Accounts)]
+#[instruction(program_id: Pubkey)]
+pub struct RegisterProgramWriter<'info> {
+    /// 数据账户的所有者（支付登记账户租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 要授权的数据账户（登记数量加一）
+    #[account(mut, constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized)]
+    pub user_pda: Account<'info, Data>,
+
+    /// 要创建的登记账户
+    #[account(
+        init,
+        payer = user,
+        seeds = [PROGRAM_WRITER_SEED, user_pda.key().as_ref(), program_id.as_ref()],
+        bump,
+        space = ProgramWriter::SPACE
+    )]
+    pub program_writer: Account<'info, ProgramWriter>,
+
+    /// 系统程序（用于创建账户）
+    pub system_program: Program<'info, System>,
+}
+
+/// UnregisterProgramWriter 指令的账户列表
+#[derive(Accounts)]
+pub struct UnregisterProgramWriter<'info> {
+    /// 数据账户的所有者（接收退还的租金）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 对应的数据账户（登记数量减一）
+    #[account(mut, constraint = user_pda.auth == user.key() @ PxsolError::Unauthorized)]
+    pub user_pda: Account<'info, Data>,
+
+    /// 要删除的登记账户
+    #[account(
+        mut,
+        seeds = [PROGRAM_WRITER_SEED, user_pda.key().as_ref(), program_writer.program_id.as_ref()],
+        bump = program_writer.bump,
+        close = user,
+    )]
+    pub program_writer: Account<'info, ProgramWriter>,
+}
+
+/// WriteAsProgram 指令的账户列表
+#[derive(Accounts)]
+#[instruction(new_data: Vec<u8>)]
+pub struct WriteAsProgram<'info> {
+    /// 调用方程序的写者 PDA（由调用方 invoke_signed 签名）
+    pub program_signer: Signer<'info>,
+
+    /// 补缴或接收租金的账户
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// 要写入的 PDA 账户
+    #[account(
+        mut,
+        realloc = Data::space_for(new_data.len()),
+        realloc::payer = payer,
+        realloc::zero = false,
+        // 缩小会把租金退给 payer，所以只有记录的 payer 才能缩小
+        constraint = user_pda.payer == payer.key()
+            || new_data.len() >= user_pda.data.len() @ PxsolError::WrongPayer,
+    )]
+    pub user_pda: Account<'info, Data>,
+
+    /// 调用方程序的登记账户
+    #[account(
+        seeds = [PROGRAM_WRITER_SEED, user_pda.key().as_ref(), program_writer.program_id.as_ref()],
+        bump = program_writer.bump,
+        constraint = program_writer.authority == program_signer.key() @ PxsolError::Unauthorized,
+    )]
+    pub program_writer: Account<'info, ProgramWriter>,
+
+    /// 系统程序（用于重新分配空间和转账）
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================
+// 账户约束定义：verify 指令
+// ============================================
+
+/// Verify 指令的账户列表
+#[derive(Accounts)]
+pub struct Verify<'info> {
+    /// 要校验的 PDA 账户
+    /// CHECK: 故意不用 Account<Data>，避免整份反序列化；
+    /// owner 由约束保证，discriminator 在指令内手动校验
+    #[account(owner = crate::ID)]
+    pub user_pda: UncheckedAccount<'info>,
+}
+
+// ============================================
+// 事件定义
+// ============================================
+// 通过 emit! 写入程序日志，链下索引服务订阅日志即可感知账户变化，无需轮询
+
+/// init 成功后触发
+#[event]
+pub struct DataInitialized {
+    pub user_pda: Pubkey,  // 新建的 PDA 地址
+    pub auth: Pubkey,      // 账户所有者
+}
+
+/// update 成功后触发
+#[event]
+pub struct DataUpdated {
+    pub user_pda: Pubkey,  // 被更新的 PDA 地址
+    pub old_len: u32,      // 更新前的数据长度
+    pub new_len: u32,      // 更新后的数据长度
+}
+
+/// patch 成功后触发
+#[event]
+pub struct DataPatched {
+    pub user_pda: Pubkey,  // 被修改的 PDA 地址
+    pub offset: u32,       // 修改起点
+    pub len: u32,          // 修改的字节数
+}
+
+/// close 成功后触发
+#[event]
+pub struct DataClosed {
+    pub user_pda: Pubkey,  // 被关闭的 PDA 地址
+    pub auth: Pubkey,      // 账户所有者
+    pub refunded: u64,     // 退还的 lamports
 }
 
 // ============================================
@@ -185,4 +1385,24 @@ pub struct Close<'info> {
 pub enum PxsolError {
     #[msg("Unauthorized: only the account owner can update data")]
     Unauthorized,
+    #[msg("Out of bounds: patch range exceeds current data length")]
+    OutOfBounds,
+    #[msg("Invalid expiry: expires_at must be in the future")]
+    InvalidExpiry,
+    #[msg("Not expired: the account has no expiry or it has not passed yet")]
+    NotExpired,
+    #[msg("Wrong payer: rent refunds must go back to the account that paid for the record")]
+    WrongPayer,
+    #[msg("Hash mismatch: on-chain data digest differs from the expected hash")]
+    HashMismatch,
+    #[msg("ACL still open: pass the acl account so it is closed together with the record")]
+    AclStillOpen,
+    #[msg("Program writer still open: pass every program_writer account in remaining_accounts")]
+    ProgramWriterStillOpen,
+    #[msg("Expiring record: LargeData has no expiry, clear expires_at before migrating")]
+    ExpiringRecord,
+    #[msg("Migration incomplete: copy all data with migrate_chunk before calling migrate")]
+    MigrationIncomplete,
+    #[msg("Not legacy: the account is not in the old auth + bump + data layout")]
+    NotLegacy,
 }
@@ -0,0 +1,745 @@
+// 使用 LiteSVM 在进程内加载编译好的程序进行测试，不需要启动本地验证器，也不需要网络
+//
+// 运行方式：
+//   anchor build      # 生成 target/deploy/lesson_5_pxsol_ss_anchor.so
+//   cargo test
+//
+// 如果还没有 anchor build，测试会直接失败并提示先编译
+
+#[path = "../../../tests/support/litesvm.rs"]
+mod support;
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, AccountDeserialize, Event, InstructionData, ToAccountMetas};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use lesson_5_pxsol_ss_anchor::{
+    accounts, instruction, Acl, AclEntry, Data, DataClosed, DataInitialized, DataUpdated, LargeData, PxsolError, Role, ID,
+};
+use litesvm::types::{TransactionMetadata, TransactionResult};
+use litesvm::LiteSVM;
+use solana_keypair::Keypair;
+use solana_sha256_hasher::hash;
+use solana_signer::Signer;
+use support::{send, send_with};
+
+// LiteSVM 默认每个签名收取 5000 lamports 手续费
+const FEE: u64 = 5000;
+
+// ============================================
+// 测试辅助函数
+// ============================================
+
+/// 创建 SVM 并加载程序，返回一个有余额的用户
+fn setup() -> (LiteSVM, Keypair) {
+    support::setup(&[(ID, "lesson_5_pxsol_ss_anchor")])
+}
+
+/// 用户的 PDA 地址：["data", 用户公钥]
+fn user_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"data", user.as_ref()], &ID).0
+}
+
+fn ix_init(user: &Pubkey) -> Instruction {
+    ix_init_with_expiry(user, None)
+}
+
+fn ix_init_with_expiry(user: &Pubkey, expires_at: Option<i64>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::Init {
+            user: *user,
+            user_pda: user_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Init { expires_at }.data(),
+    }
+}
+
+fn ix_update(user: &Pubkey, pda: Pubkey, data: Vec<u8>) -> Instruction {
+    ix_update_with_acl(user, pda, None, data)
+}
+
+fn ix_update_with_acl(user: &Pubkey, pda: Pubkey, acl: Option<Pubkey>, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::Update {
+            user: *user,
+            user_pda: pda,
+            system_program: system_program::ID,
+            acl,
+        }
+        .to_account_metas(None),
+        data: instruction::Update { data, expires_at: None }.data(),
+    }
+}
+
+/// 数据账户的 ACL 地址：["acl", 数据账户地址]
+fn acl_pda(pda: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"acl", pda.as_ref()], &ID).0
+}
+
+fn ix_init_acl(user: &Pubkey, pda: Pubkey, writers: Vec<AclEntry>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitAcl {
+            user: *user,
+            user_pda: pda,
+            acl: acl_pda(&pda),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitAcl { readers: vec![], writers }.data(),
+    }
+}
+
+fn ix_set_acl(user: &Pubkey, pda: Pubkey, writers: Vec<AclEntry>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::SetAcl {
+            user: *user,
+            user_pda: pda,
+            acl: acl_pda(&pda),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::SetAcl { readers: vec![], writers }.data(),
+    }
+}
+
+fn ix_close(user: &Pubkey, pda: Pubkey) -> Instruction {
+    ix_close_to(user, pda, *user)
+}
+
+fn ix_close_to(user: &Pubkey, pda: Pubkey, payer: Pubkey) -> Instruction {
+    ix_close_with_acl(user, pda, payer, None)
+}
+
+fn ix_close_with_acl(user: &Pubkey, pda: Pubkey, payer: Pubkey, acl: Option<Pubkey>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::Close {
+            user: *user,
+            user_pda: pda,
+            payer,
+            acl,
+        }
+        .to_account_metas(None),
+        data: instruction::Close {}.data(),
+    }
+}
+
+fn ix_transfer_auth(user: &Pubkey, pda: Pubkey, new_auth: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::TransferAuth { user: *user, user_pda: pda }.to_account_metas(None),
+        data: instruction::TransferAuth { new_auth }.data(),
+    }
+}
+
+fn ix_patch(user: &Pubkey, pda: Pubkey, offset: u32, bytes: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::Patch { user: *user, user_pda: pda }.to_account_metas(None),
+        data: instruction::Patch { offset, bytes }.data(),
+    }
+}
+
+fn ix_resize(user: &Pubkey, pda: Pubkey, new_len: u32) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::Resize {
+            user: *user,
+            user_pda: pda,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Resize { new_len }.data(),
+    }
+}
+
+/// 用户的大记录地址：["large", 用户公钥]
+fn large_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"large", user.as_ref()], &ID).0
+}
+
+fn ix_init_large(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitLarge {
+            user: *user,
+            large_pda: large_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitLarge {}.data(),
+    }
+}
+
+fn ix_migrate_chunk(user: &Pubkey, offset: u32, len: u32) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::MigrateChunk {
+            user: *user,
+            user_pda: user_pda(user),
+            large_pda: large_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::MigrateChunk { offset, len }.data(),
+    }
+}
+
+fn ix_migrate(user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::Migrate {
+            user: *user,
+            user_pda: user_pda(user),
+            large_pda: large_pda(user),
+        }
+        .to_account_metas(None),
+        data: instruction::Migrate {}.data(),
+    }
+}
+
+fn ix_verify(pda: Pubkey, expected_hash: [u8; 32]) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::Verify { user_pda: pda }.to_account_metas(None),
+        data: instruction::Verify { expected_hash }.data(),
+    }
+}
+
+fn ix_reap(pda: Pubkey, payer: Pubkey, auth: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::Reap {
+            user_pda: pda,
+            payer,
+            auth,
+            acl: None,
+        }
+        .to_account_metas(None),
+        data: instruction::Reap {}.data(),
+    }
+}
+
+fn ix_init_sponsored(sponsor: &Pubkey, user: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitSponsored {
+            sponsor: *sponsor,
+            user: *user,
+            user_pda: user_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitSponsored { expires_at: None }.data(),
+    }
+}
+
+fn ix_update_sponsored(sponsor: &Pubkey, user: &Pubkey, pda: Pubkey, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::UpdateSponsored {
+            sponsor: *sponsor,
+            user: *user,
+            user_pda: pda,
+            system_program: system_program::ID,
+            acl: None,
+        }
+        .to_account_metas(None),
+        data: instruction::UpdateSponsored { data, expires_at: None }.data(),
+    }
+}
+
+fn ix_upgrade_legacy(user: &Pubkey, pda: Pubkey) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::UpgradeLegacy {
+            user: *user,
+            user_pda: pda,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::UpgradeLegacy {}.data(),
+    }
+}
+
+/// 读取并反序列化 PDA 账户
+fn load_data(svm: &LiteSVM, pda: &Pubkey) -> Data {
+    let account = svm.get_account(pda).unwrap();
+    Data::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+/// 断言交易因 PxsolError 失败
+fn assert_pxsol_error(result: TransactionResult, error: PxsolError) {
+    let err = result.unwrap_err().err;
+    let expected = format!("Custom({})", u32::from(error));
+    assert!(format!("{err:?}").contains(&expected), "unexpected error: {err:?}");
+}
+
+/// 断言日志中有 emit! 写入的事件："Program data: " + base64(discriminator + Borsh 编码的字段)
+fn assert_event(meta: &TransactionMetadata, event: impl Event) {
+    let expected = format!("Program data: {}", STANDARD.encode(event.data()));
+    assert!(meta.logs.iter().any(|log| log == &expected), "{expected} not in logs: {:#?}", meta.logs);
+}
+
+// ============================================
+// 测试用例
+// ============================================
+
+#[test]
+fn test_init() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+
+    let meta = send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    assert_event(&meta, DataInitialized { user_pda: pda, auth: user.pubkey() });
+
+    let account = svm.get_account(&pda).unwrap();
+    assert_eq!(account.owner, ID);
+    assert_eq!(account.data.len(), Data::space_for(0));
+    assert_eq!(account.lamports, svm.minimum_balance_for_rent_exemption(Data::space_for(0)));
+
+    let data = load_data(&svm, &pda);
+    assert_eq!(data.auth, user.pubkey());
+    assert!(data.data.is_empty());
+}
+
+#[test]
+fn test_update_grow() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+
+    let payload = b"The quick brown fox jumps over the lazy dog".to_vec();
+    let meta = send(&mut svm, &user, ix_update(&user.pubkey(), pda, payload.clone())).unwrap();
+    assert_event(&meta, DataUpdated { user_pda: pda, old_len: 0, new_len: payload.len() as u32 });
+
+    // 扩容后空间和租金都按新长度计算
+    let account = svm.get_account(&pda).unwrap();
+    assert_eq!(account.data.len(), Data::space_for(payload.len()));
+    assert_eq!(account.lamports, svm.minimum_balance_for_rent_exemption(Data::space_for(payload.len())));
+    assert_eq!(load_data(&svm, &pda).data, payload);
+}
+
+#[test]
+fn test_update_shrink_refund() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    send(&mut svm, &user, ix_update(&user.pubkey(), pda, vec![7; 200])).unwrap();
+
+    let hold = svm.get_balance(&pda).unwrap();
+    let user_before = svm.get_balance(&user.pubkey()).unwrap();
+
+    let meta = send(&mut svm, &user, ix_update(&user.pubkey(), pda, vec![9; 10])).unwrap();
+    assert_event(&meta, DataUpdated { user_pda: pda, old_len: 200, new_len: 10 });
+
+    // 缩小后 PDA 只保留免租金额，多出的 hold - rent_exemption 退还给用户
+    let rent_exemption = svm.minimum_balance_for_rent_exemption(Data::space_for(10));
+    assert_eq!(svm.get_balance(&pda).unwrap(), rent_exemption);
+    assert_eq!(
+        svm.get_balance(&user.pubkey()).unwrap(),
+        user_before - FEE + (hold - rent_exemption)
+    );
+    assert_eq!(load_data(&svm, &pda).data, vec![9; 10]);
+}
+
+#[test]
+fn test_update_unauthorized() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    send(&mut svm, &user, ix_update(&user.pubkey(), pda, b"mine".to_vec())).unwrap();
+
+    let attacker = Keypair::new();
+    svm.airdrop(&attacker.pubkey(), 1_000_000_000).unwrap();
+    let result = send(&mut svm, &attacker, ix_update(&attacker.pubkey(), pda, b"stolen".to_vec()));
+
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+    assert_eq!(load_data(&svm, &pda).data, b"mine".to_vec());
+}
+
+#[test]
+fn test_close() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    send(&mut svm, &user, ix_update(&user.pubkey(), pda, vec![1; 64])).unwrap();
+
+    // 非所有者不能关闭（payer 传记录真正的支付者，这样触发的是 auth 检查，而不是 has_one = payer）
+    let attacker = Keypair::new();
+    svm.airdrop(&attacker.pubkey(), 1_000_000_000).unwrap();
+    let result = send(&mut svm, &attacker, ix_close_to(&attacker.pubkey(), pda, user.pubkey()));
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+
+    // 所有者关闭后，全部租金退回
+    let hold = svm.get_balance(&pda).unwrap();
+    let user_before = svm.get_balance(&user.pubkey()).unwrap();
+    let meta = send(&mut svm, &user, ix_close(&user.pubkey(), pda)).unwrap();
+    assert_event(&meta, DataClosed { user_pda: pda, auth: user.pubkey(), refunded: hold });
+
+    assert!(svm.get_account(&pda).is_none_or(|account| account.lamports == 0));
+    assert_eq!(svm.get_balance(&user.pubkey()).unwrap(), user_before - FEE + hold);
+}
+
+#[test]
+fn test_transfer_auth() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    send(&mut svm, &user, ix_update(&user.pubkey(), pda, b"old key".to_vec())).unwrap();
+
+    let new_auth = Keypair::new();
+    svm.airdrop(&new_auth.pubkey(), 1_000_000_000).unwrap();
+    send(&mut svm, &user, ix_transfer_auth(&user.pubkey(), pda, new_auth.pubkey())).unwrap();
+
+    // 租金是用户自己付的，payer 跟着转给新钱包；PDA 地址不变
+    let data = load_data(&svm, &pda);
+    assert_eq!(data.auth, new_auth.pubkey());
+    assert_eq!(data.payer, new_auth.pubkey());
+
+    // 旧钱包不能再更新、转移或关闭（payer 传记录真正的支付者，拒绝来自 auth 检查）
+    let result = send(&mut svm, &user, ix_update(&user.pubkey(), pda, b"stale".to_vec()));
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+    let result = send(&mut svm, &user, ix_transfer_auth(&user.pubkey(), pda, user.pubkey()));
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+    let result = send(&mut svm, &user, ix_close_to(&user.pubkey(), pda, new_auth.pubkey()));
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+
+    // 新钱包可以更新，缩小时退款也归新钱包
+    send(&mut svm, &new_auth, ix_update(&new_auth.pubkey(), pda, b"new key".to_vec())).unwrap();
+    assert_eq!(load_data(&svm, &pda).data, b"new key".to_vec());
+
+    // 关闭时租金只能退给新的 payer
+    let result = send(&mut svm, &new_auth, ix_close_to(&new_auth.pubkey(), pda, user.pubkey()));
+    assert_pxsol_error(result, PxsolError::WrongPayer);
+    let hold = svm.get_balance(&pda).unwrap();
+    let new_auth_before = svm.get_balance(&new_auth.pubkey()).unwrap();
+    send(&mut svm, &new_auth, ix_close(&new_auth.pubkey(), pda)).unwrap();
+    assert_eq!(svm.get_balance(&new_auth.pubkey()).unwrap(), new_auth_before - FEE + hold);
+}
+
+#[test]
+fn test_transfer_auth_sponsored() {
+    let (mut svm, sponsor) = setup();
+    let user = Keypair::new();
+    svm.airdrop(&user.pubkey(), 1_000_000_000).unwrap();
+    let pda = user_pda(&user.pubkey());
+    send_with(&mut svm, &[&sponsor, &user], ix_init_sponsored(&sponsor.pubkey(), &user.pubkey())).unwrap();
+    let ix = ix_update_sponsored(&sponsor.pubkey(), &user.pubkey(), pda, vec![5; 100]);
+    send_with(&mut svm, &[&sponsor, &user], ix).unwrap();
+
+    // 赞助商付的租金仍归赞助商，payer 不跟着转移
+    let new_auth = Keypair::new();
+    svm.airdrop(&new_auth.pubkey(), 1_000_000_000).unwrap();
+    send(&mut svm, &user, ix_transfer_auth(&user.pubkey(), pda, new_auth.pubkey())).unwrap();
+    let data = load_data(&svm, &pda);
+    assert_eq!(data.auth, new_auth.pubkey());
+    assert_eq!(data.payer, sponsor.pubkey());
+
+    // 新钱包不能通过缩小或关闭拿走赞助商的租金
+    let result = send(&mut svm, &new_auth, ix_update(&new_auth.pubkey(), pda, vec![5; 10]));
+    assert_pxsol_error(result, PxsolError::WrongPayer);
+    let result = send(&mut svm, &new_auth, ix_close(&new_auth.pubkey(), pda));
+    assert_pxsol_error(result, PxsolError::WrongPayer);
+
+    let hold = svm.get_balance(&pda).unwrap();
+    let sponsor_before = svm.get_balance(&sponsor.pubkey()).unwrap();
+    send(&mut svm, &new_auth, ix_close_to(&new_auth.pubkey(), pda, sponsor.pubkey())).unwrap();
+    assert_eq!(svm.get_balance(&sponsor.pubkey()).unwrap(), sponsor_before + hold);
+}
+
+#[test]
+fn test_acl_editor() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+
+    let editor = Keypair::new();
+    svm.airdrop(&editor.pubkey(), 1_000_000_000).unwrap();
+    let writers = vec![AclEntry { key: editor.pubkey(), role: Role::Editor }];
+    send(&mut svm, &user, ix_init_acl(&user.pubkey(), pda, writers)).unwrap();
+
+    // 编辑者带上 ACL 可以更新
+    let acl = Some(acl_pda(&pda));
+    send(&mut svm, &editor, ix_update_with_acl(&editor.pubkey(), pda, acl, b"shared".to_vec())).unwrap();
+    assert_eq!(load_data(&svm, &pda).data, b"shared".to_vec());
+
+    // 编辑者不能关闭（payer 传记录真正的支付者，确保拒绝来自 auth 检查）
+    let result = send(&mut svm, &editor, ix_close_to(&editor.pubkey(), pda, user.pubkey()));
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+
+    // 不在 ACL 中的人即使带上 ACL 也会被拒绝
+    let stranger = Keypair::new();
+    svm.airdrop(&stranger.pubkey(), 1_000_000_000).unwrap();
+    let result = send(&mut svm, &stranger, ix_update_with_acl(&stranger.pubkey(), pda, acl, b"x".to_vec()));
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+}
+
+#[test]
+fn test_acl_owner_cannot_shrink() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+
+    let owner = Keypair::new();
+    svm.airdrop(&owner.pubkey(), 1_000_000_000).unwrap();
+    let editor = AclEntry { key: Keypair::new().pubkey(), role: Role::Editor };
+    let writers = vec![AclEntry { key: owner.pubkey(), role: Role::Owner }, editor];
+    send(&mut svm, &user, ix_init_acl(&user.pubkey(), pda, writers)).unwrap();
+    let acl = acl_pda(&pda);
+
+    // Owner 可以修改列表，但缩小会把 auth 付的租金退给自己，必须拒绝
+    let owner_only = vec![AclEntry { key: owner.pubkey(), role: Role::Owner }];
+    let result = send(&mut svm, &owner, ix_set_acl(&owner.pubkey(), pda, owner_only.clone()));
+    assert_pxsol_error(result, PxsolError::WrongPayer);
+    let other = AclEntry { key: Keypair::new().pubkey(), role: Role::Editor };
+    let swapped = vec![AclEntry { key: owner.pubkey(), role: Role::Owner }, other];
+    send(&mut svm, &owner, ix_set_acl(&owner.pubkey(), pda, swapped)).unwrap();
+
+    // auth 缩小时退款回到 auth
+    let hold = svm.get_balance(&acl).unwrap();
+    let user_before = svm.get_balance(&user.pubkey()).unwrap();
+    send(&mut svm, &user, ix_set_acl(&user.pubkey(), pda, owner_only)).unwrap();
+    let rent_exemption = svm.minimum_balance_for_rent_exemption(Acl::space_for(0, 1));
+    assert_eq!(svm.get_balance(&acl).unwrap(), rent_exemption);
+    assert_eq!(svm.get_balance(&user.pubkey()).unwrap(), user_before - FEE + (hold - rent_exemption));
+}
+
+#[test]
+fn test_close_with_acl() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+
+    let editor = Keypair::new();
+    svm.airdrop(&editor.pubkey(), 1_000_000_000).unwrap();
+    let writers = vec![AclEntry { key: editor.pubkey(), role: Role::Editor }];
+    send(&mut svm, &user, ix_init_acl(&user.pubkey(), pda, writers)).unwrap();
+    let acl = acl_pda(&pda);
+
+    // 存在 ACL 时不传入 ACL 不能关闭
+    let result = send(&mut svm, &user, ix_close(&user.pubkey(), pda));
+    assert_pxsol_error(result, PxsolError::AclStillOpen);
+
+    // 一起关闭，两个账户的租金都退还给 user
+    let hold = svm.get_balance(&pda).unwrap() + svm.get_balance(&acl).unwrap();
+    let user_before = svm.get_balance(&user.pubkey()).unwrap();
+    send(&mut svm, &user, ix_close_with_acl(&user.pubkey(), pda, user.pubkey(), Some(acl))).unwrap();
+    assert!(svm.get_account(&acl).is_none_or(|account| account.lamports == 0));
+    assert_eq!(svm.get_balance(&user.pubkey()).unwrap(), user_before - FEE + hold);
+
+    // 同一钱包重新 init 后，旧的编辑者不能再写入
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    assert!(!load_data(&svm, &pda).has_acl);
+    let result = send(&mut svm, &editor, ix_update_with_acl(&editor.pubkey(), pda, Some(acl), b"stale".to_vec()));
+    assert!(result.is_err());
+    let result = send(&mut svm, &editor, ix_update(&editor.pubkey(), pda, b"stale".to_vec()));
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+    assert!(load_data(&svm, &pda).data.is_empty());
+}
+
+#[test]
+fn test_reap_after_expiry() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    send(&mut svm, &user, ix_init_with_expiry(&user.pubkey(), Some(now + 60))).unwrap();
+    send(&mut svm, &user, ix_update(&user.pubkey(), pda, vec![3; 32])).unwrap();
+
+    // 任何人都可以提交 reap，但未过期时会被拒绝
+    let reaper = Keypair::new();
+    svm.airdrop(&reaper.pubkey(), 1_000_000_000).unwrap();
+    let result = send(&mut svm, &reaper, ix_reap(pda, user.pubkey(), user.pubkey()));
+    assert_pxsol_error(result, PxsolError::NotExpired);
+
+    // 时间推进到过期之后，租金全部退还给最初的 payer
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = now + 60;
+    svm.set_sysvar(&clock);
+
+    let hold = svm.get_balance(&pda).unwrap();
+    let payer_before = svm.get_balance(&user.pubkey()).unwrap();
+    send(&mut svm, &reaper, ix_reap(pda, user.pubkey(), user.pubkey())).unwrap();
+
+    assert!(svm.get_account(&pda).is_none_or(|account| account.lamports == 0));
+    assert_eq!(svm.get_balance(&user.pubkey()).unwrap(), payer_before + hold);
+}
+
+#[test]
+fn test_sponsored_rent() {
+    let (mut svm, sponsor) = setup();
+    // 终端用户没有任何 SOL
+    let user = Keypair::new();
+    let pda = user_pda(&user.pubkey());
+
+    send_with(&mut svm, &[&sponsor, &user], ix_init_sponsored(&sponsor.pubkey(), &user.pubkey())).unwrap();
+    let data = load_data(&svm, &pda);
+    assert_eq!(data.auth, user.pubkey());
+    assert_eq!(data.payer, sponsor.pubkey());
+
+    // 扩容由赞助商补缴
+    let ix = ix_update_sponsored(&sponsor.pubkey(), &user.pubkey(), pda, vec![5; 300]);
+    send_with(&mut svm, &[&sponsor, &user], ix).unwrap();
+
+    // 用户自己缩小会把赞助商的租金退给自己，必须拒绝
+    svm.airdrop(&user.pubkey(), 1_000_000_000).unwrap();
+    let result = send(&mut svm, &user, ix_update(&user.pubkey(), pda, vec![5; 10]));
+    assert_pxsol_error(result, PxsolError::WrongPayer);
+
+    // 通过 update_sponsored 缩小，退款回到赞助商
+    let hold = svm.get_balance(&pda).unwrap();
+    let sponsor_before = svm.get_balance(&sponsor.pubkey()).unwrap();
+    let ix = ix_update_sponsored(&sponsor.pubkey(), &user.pubkey(), pda, vec![5; 10]);
+    send_with(&mut svm, &[&sponsor, &user], ix).unwrap();
+    let rent_exemption = svm.minimum_balance_for_rent_exemption(Data::space_for(10));
+    // 两个签名，手续费为 2 * FEE
+    assert_eq!(
+        svm.get_balance(&sponsor.pubkey()).unwrap(),
+        sponsor_before - 2 * FEE + (hold - rent_exemption)
+    );
+
+    // 关闭时租金也回到赞助商，传入其他地址会被拒绝
+    let result = send(&mut svm, &user, ix_close_to(&user.pubkey(), pda, user.pubkey()));
+    assert_pxsol_error(result, PxsolError::WrongPayer);
+
+    let sponsor_before = svm.get_balance(&sponsor.pubkey()).unwrap();
+    send(&mut svm, &user, ix_close_to(&user.pubkey(), pda, sponsor.pubkey())).unwrap();
+    assert_eq!(svm.get_balance(&sponsor.pubkey()).unwrap(), sponsor_before + rent_exemption);
+}
+
+#[test]
+fn test_hash_and_verify() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    assert_eq!(load_data(&svm, &pda).hash, hash(&[]).to_bytes());
+
+    let payload = b"The quick brown fox jumps over the lazy dog".to_vec();
+    send(&mut svm, &user, ix_update(&user.pubkey(), pda, payload.clone())).unwrap();
+    let digest = hash(&payload).to_bytes();
+    assert_eq!(load_data(&svm, &pda).hash, digest);
+
+    // 摘要一致时通过，不一致时失败
+    send(&mut svm, &user, ix_verify(pda, digest)).unwrap();
+    let result = send(&mut svm, &user, ix_verify(pda, hash(b"stale copy").to_bytes()));
+    assert_pxsol_error(result, PxsolError::HashMismatch);
+}
+
+#[test]
+fn test_patch_and_resize_refresh_hash() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    send(&mut svm, &user, ix_update(&user.pubkey(), pda, b"hello world".to_vec())).unwrap();
+
+    // patch 直接改原始字节，摘要也要跟着更新
+    send(&mut svm, &user, ix_patch(&user.pubkey(), pda, 6, b"WORLD".to_vec())).unwrap();
+    let data = load_data(&svm, &pda);
+    assert_eq!(data.data, b"hello WORLD".to_vec());
+    assert_eq!(data.hash, hash(b"hello WORLD").to_bytes());
+
+    // 扩容补零、缩小截断后摘要都按新数据计算
+    send(&mut svm, &user, ix_resize(&user.pubkey(), pda, 16)).unwrap();
+    let data = load_data(&svm, &pda);
+    assert_eq!(data.data, b"hello WORLD\0\0\0\0\0".to_vec());
+    assert_eq!(data.hash, hash(&data.data).to_bytes());
+
+    send(&mut svm, &user, ix_resize(&user.pubkey(), pda, 5)).unwrap();
+    let data = load_data(&svm, &pda);
+    assert_eq!(data.data, b"hello".to_vec());
+    assert_eq!(data.hash, hash(b"hello").to_bytes());
+    send(&mut svm, &user, ix_verify(pda, hash(b"hello").to_bytes())).unwrap();
+}
+
+#[test]
+fn test_migrate_large_record() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+
+    // 每条指令最多扩容 10 KB，分两次把记录扩到 20000 字节，再在两端写入内容
+    send(&mut svm, &user, ix_resize(&user.pubkey(), pda, 10_000)).unwrap();
+    send(&mut svm, &user, ix_resize(&user.pubkey(), pda, 20_000)).unwrap();
+    send(&mut svm, &user, ix_patch(&user.pubkey(), pda, 0, b"head".to_vec())).unwrap();
+    send(&mut svm, &user, ix_patch(&user.pubkey(), pda, 19_996, b"tail".to_vec())).unwrap();
+    let expected = load_data(&svm, &pda).data;
+
+    // 先创建只有头部的大记录，复制一半时不能完成迁移
+    send(&mut svm, &user, ix_init_large(&user.pubkey())).unwrap();
+    send(&mut svm, &user, ix_migrate_chunk(&user.pubkey(), 0, 10_000)).unwrap();
+    let result = send(&mut svm, &user, ix_migrate(&user.pubkey()));
+    assert_pxsol_error(result, PxsolError::MigrationIncomplete);
+
+    // 复制剩余部分后完成迁移，旧账户关闭
+    send(&mut svm, &user, ix_migrate_chunk(&user.pubkey(), 10_000, 10_000)).unwrap();
+    send(&mut svm, &user, ix_migrate(&user.pubkey())).unwrap();
+    assert!(svm.get_account(&pda).is_none_or(|account| account.lamports == 0));
+
+    let large = svm.get_account(&large_pda(&user.pubkey())).unwrap();
+    assert_eq!(large.data[LargeData::TAIL_OFFSET..], expected[..]);
+    assert_eq!(large.lamports, svm.minimum_balance_for_rent_exemption(LargeData::space_for(20_000)));
+}
+
+#[test]
+fn test_migrate_rejects_expiring_record() {
+    let (mut svm, user) = setup();
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+    send(&mut svm, &user, ix_init_with_expiry(&user.pubkey(), Some(now + 60))).unwrap();
+    send(&mut svm, &user, ix_init_large(&user.pubkey())).unwrap();
+
+    // LargeData 没有过期时间，迁移会悄悄丢掉 reap，必须先取消过期
+    let result = send(&mut svm, &user, ix_migrate(&user.pubkey()));
+    assert_pxsol_error(result, PxsolError::ExpiringRecord);
+}
+
+#[test]
+fn test_upgrade_legacy_record() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+
+    // 当前布局的记录不能当作旧记录升级
+    let result = send(&mut svm, &user, ix_upgrade_legacy(&user.pubkey(), pda));
+    assert_pxsol_error(result, PxsolError::NotLegacy);
+
+    // 把账户改写成旧布局：discriminator + auth + bump + data
+    let payload = b"written before expiry".to_vec();
+    let mut account = svm.get_account(&pda).unwrap();
+    let mut legacy = account.data[..Data::LEGACY_DATA_OFFSET - 4].to_vec();
+    legacy.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    legacy.extend_from_slice(&payload);
+    account.lamports = svm.minimum_balance_for_rent_exemption(legacy.len());
+    account.data = legacy;
+    svm.set_account(pda, account).unwrap();
+
+    // 旧记录不能按当前布局反序列化，close 会失败
+    assert!(send(&mut svm, &user, ix_close(&user.pubkey(), pda)).is_err());
+
+    // 只有 auth 可以升级
+    let attacker = Keypair::new();
+    svm.airdrop(&attacker.pubkey(), 1_000_000_000).unwrap();
+    let result = send(&mut svm, &attacker, ix_upgrade_legacy(&attacker.pubkey(), pda));
+    assert_pxsol_error(result, PxsolError::Unauthorized);
+
+    send(&mut svm, &user, ix_upgrade_legacy(&user.pubkey(), pda)).unwrap();
+    let data = load_data(&svm, &pda);
+    assert_eq!(data.auth, user.pubkey());
+    assert_eq!(data.payer, user.pubkey());
+    assert_eq!(data.expires_at, 0);
+    assert_eq!(data.data, payload);
+    assert_eq!(data.hash, hash(&payload).to_bytes());
+    let rent_exemption = svm.minimum_balance_for_rent_exemption(Data::space_for(payload.len()));
+    assert_eq!(svm.get_balance(&pda).unwrap(), rent_exemption);
+
+    // 升级后可以照常关闭，租金全部退回
+    let user_before = svm.get_balance(&user.pubkey()).unwrap();
+    send(&mut svm, &user, ix_close(&user.pubkey(), pda)).unwrap();
+    assert_eq!(svm.get_balance(&user.pubkey()).unwrap(), user_before - FEE + rent_exemption);
+}
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use lesson_5_pxsol_ss_anchor::cpi::accounts::{Update, WriteAsProgram};
+use lesson_5_pxsol_ss_anchor::program::Lesson5PxsolSsAnchor;
+use lesson_5_pxsol_ss_anchor::{Data, ProgramWriter, WRITER_AUTHORITY_SEED};
+
+// 程序 ID（部署后的链上地址）
+declare_id!("4mSZfFsd1RWBVwuBgm3dkAbstWxc6hEMzacknZBYphfw");
+
+// ============================================
+// 示例程序：通过 CPI 写入 lesson-5-pxsol-ss-anchor 的数据账户
+// - 依赖 lesson-5-pxsol-ss-anchor 的 cpi feature，直接使用生成的 cpi 模块
+// ============================================
+#[program]
+pub mod pxsol_ss_caller {
+    use super::*;
+
+    /// 指令 1：代用户调用 update
+    /// - 用户在外层交易中签名，签名权限会随 CPI 传递给被调用程序
+    pub fn proxy_update(ctx: Context<ProxyUpdate>, data: Vec<u8>) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            ctx.accounts.pxsol_program.to_account_info(),
+            Update {
+                user: ctx.accounts.user.to_account_info(),
+                user_pda: ctx.accounts.user_pda.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                acl: None,
+            },
+        );
+        lesson_5_pxsol_ss_anchor::cpi::update(cpi_ctx, data, None)
+    }
+
+    /// 指令 2：以本程序的身份调用 write_as_program
+    /// - 用户不需要签名，只要数据账户的 auth 事先登记过本程序
+    /// - 本程序用 ["writer"] PDA 通过 invoke_signed 签名
+    pub fn proxy_write(ctx: Context<ProxyWrite>, data: Vec<u8>) -> Result<()> {
+        let signer_seeds: &[&[&[u8]]] = &[&[WRITER_AUTHORITY_SEED, &[ctx.bumps.writer]]];
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.pxsol_program.to_account_info(),
+            WriteAsProgram {
+                program_signer: ctx.accounts.writer.to_account_info(),
+                payer: ctx.accounts.payer.to_account_info(),
+                user_pda: ctx.accounts.user_pda.to_account_info(),
+                program_writer: ctx.accounts.program_writer.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+            },
+            signer_seeds,
+        );
+        lesson_5_pxsol_ss_anchor::cpi::write_as_program(cpi_ctx, data)
+    }
+}
+
+// ============================================
+// 账户约束定义：proxy_update 指令
+// ============================================
+
+/// ProxyUpdate 指令的账户列表
+#[derive(Accounts)]
+pub struct ProxyUpdate<'info> {
+    /// 数据账户的所有者（签名随 CPI 传递）
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// 要更新的数据账户（权限由被调用程序校验）
+    #[account(mut)]
+    pub user_pda: Account<'info, Data>,
+
+    /// 系统程序（被调用程序 realloc 时使用）
+    pub system_program: Program<'info, System>,
+
+    /// 被调用的 lesson-5-pxsol-ss-anchor 程序
+    pub pxsol_program: Program<'info, Lesson5PxsolSsAnchor>,
+}
+
+// ============================================
+// 账户约束定义：proxy_write 指令
+// ============================================
+
+/// ProxyWrite 指令的账户列表
+#[derive(Accounts)]
+pub struct ProxyWrite<'info> {
+    /// 本程序的写者 PDA（不需要真实存在，只用来签名）
+    /// CHECK: 地址由 seeds 校验，只作为 CPI 签名者
+    #[account(seeds = [WRITER_AUTHORITY_SEED], bump)]
+    pub writer: UncheckedAccount<'info>,
+
+    /// 补缴或接收租金的账户
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// 要写入的数据账户
+    #[account(mut)]
+    pub user_pda: Account<'info, Data>,
+
+    /// 本程序在该数据账户上的登记（由被调用程序校验）
+    pub program_writer: Account<'info, ProgramWriter>,
+
+    /// 系统程序（被调用程序 realloc 时使用）
+    pub system_program: Program<'info, System>,
+
+    /// 被调用的 lesson-5-pxsol-ss-anchor 程序
+    pub pxsol_program: Program<'info, Lesson5PxsolSsAnchor>,
+}
@@ -0,0 +1,203 @@
+// 示例：第二个程序通过 CPI 写入 lesson-5-pxsol-ss-anchor 的数据账户
+//
+// 运行方式：
+//   anchor build      # 生成 target/deploy 下的两个 .so
+//   cargo test
+//
+// 如果还没有 anchor build，测试会直接失败并提示先编译
+
+#[path = "../../../tests/support/litesvm.rs"]
+mod support;
+
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use lesson_5_pxsol_ss_anchor::{Data, PxsolError, WRITER_AUTHORITY_SEED};
+use litesvm::LiteSVM;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use support::send;
+
+// ============================================
+// 测试辅助函数
+// ============================================
+
+/// 创建 SVM 并加载两个程序，返回一个有余额的用户
+fn setup() -> (LiteSVM, Keypair) {
+    support::setup(&[
+        (lesson_5_pxsol_ss_anchor::ID, "lesson_5_pxsol_ss_anchor"),
+        (pxsol_ss_caller::ID, "pxsol_ss_caller"),
+    ])
+}
+
+fn user_pda(user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"data", user.as_ref()], &lesson_5_pxsol_ss_anchor::ID).0
+}
+
+fn program_writer_pda(pda: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"program_writer", pda.as_ref(), pxsol_ss_caller::ID.as_ref()],
+        &lesson_5_pxsol_ss_anchor::ID,
+    )
+    .0
+}
+
+fn writer_authority() -> Pubkey {
+    Pubkey::find_program_address(&[WRITER_AUTHORITY_SEED], &pxsol_ss_caller::ID).0
+}
+
+/// 直接调用 lesson-5-pxsol-ss-anchor 的 init
+fn ix_init(user: &Pubkey) -> Instruction {
+    use lesson_5_pxsol_ss_anchor::{accounts, instruction};
+    Instruction {
+        program_id: lesson_5_pxsol_ss_anchor::ID,
+        accounts: accounts::Init {
+            user: *user,
+            user_pda: user_pda(user),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::Init { expires_at: None }.data(),
+    }
+}
+
+/// 直接调用 lesson-5-pxsol-ss-anchor 的 register_program_writer，登记调用方程序
+fn ix_register(user: &Pubkey) -> Instruction {
+    use lesson_5_pxsol_ss_anchor::{accounts, instruction};
+    let pda = user_pda(user);
+    Instruction {
+        program_id: lesson_5_pxsol_ss_anchor::ID,
+        accounts: accounts::RegisterProgramWriter {
+            user: *user,
+            user_pda: pda,
+            program_writer: program_writer_pda(&pda),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::RegisterProgramWriter { program_id: pxsol_ss_caller::ID }.data(),
+    }
+}
+
+/// 直接调用 lesson-5-pxsol-ss-anchor 的 close，writers 为需要一起关闭的登记账户
+fn ix_close(user: &Pubkey, writers: &[Pubkey]) -> Instruction {
+    use lesson_5_pxsol_ss_anchor::{accounts, instruction};
+    let mut accounts = accounts::Close {
+        user: *user,
+        user_pda: user_pda(user),
+        payer: *user,
+        acl: None,
+    }
+    .to_account_metas(None);
+    accounts.extend(writers.iter().map(|writer| AccountMeta::new(*writer, false)));
+    Instruction {
+        program_id: lesson_5_pxsol_ss_anchor::ID,
+        accounts,
+        data: instruction::Close {}.data(),
+    }
+}
+
+/// 通过调用方程序 CPI 调用 update
+fn ix_proxy_update(user: &Pubkey, data: Vec<u8>) -> Instruction {
+    use pxsol_ss_caller::{accounts, instruction};
+    Instruction {
+        program_id: pxsol_ss_caller::ID,
+        accounts: accounts::ProxyUpdate {
+            user: *user,
+            user_pda: user_pda(user),
+            system_program: system_program::ID,
+            pxsol_program: lesson_5_pxsol_ss_anchor::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ProxyUpdate { data }.data(),
+    }
+}
+
+/// 通过调用方程序 CPI 调用 write_as_program
+fn ix_proxy_write(payer: &Pubkey, pda: Pubkey, data: Vec<u8>) -> Instruction {
+    use pxsol_ss_caller::{accounts, instruction};
+    Instruction {
+        program_id: pxsol_ss_caller::ID,
+        accounts: accounts::ProxyWrite {
+            writer: writer_authority(),
+            payer: *payer,
+            user_pda: pda,
+            program_writer: program_writer_pda(&pda),
+            system_program: system_program::ID,
+            pxsol_program: lesson_5_pxsol_ss_anchor::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::ProxyWrite { data }.data(),
+    }
+}
+
+fn load_data(svm: &LiteSVM, pda: &Pubkey) -> Data {
+    let account = svm.get_account(pda).unwrap();
+    Data::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+// ============================================
+// 测试用例
+// ============================================
+
+#[test]
+fn test_cpi_update_with_user_signer() {
+    let (mut svm, user) = setup();
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+
+    // 用户只对外层交易签名，签名权限随 CPI 传给 update
+    send(&mut svm, &user, ix_proxy_update(&user.pubkey(), b"via cpi".to_vec())).unwrap();
+    assert_eq!(load_data(&svm, &user_pda(&user.pubkey())).data, b"via cpi".to_vec());
+}
+
+#[test]
+fn test_write_as_registered_program() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+
+    // 由另一个账户支付租金，用户本人不参与签名
+    let relayer = Keypair::new();
+    svm.airdrop(&relayer.pubkey(), 1_000_000_000).unwrap();
+
+    // 未登记时，程序签名不被接受（登记账户不存在）
+    let result = send(&mut svm, &relayer, ix_proxy_write(&relayer.pubkey(), pda, b"early".to_vec()));
+    assert!(result.is_err());
+
+    // 登记之后可以写入
+    send(&mut svm, &user, ix_register(&user.pubkey())).unwrap();
+    send(&mut svm, &relayer, ix_proxy_write(&relayer.pubkey(), pda, b"from program".to_vec())).unwrap();
+    assert_eq!(load_data(&svm, &pda).data, b"from program".to_vec());
+
+    // relayer 不是记录的 payer，不能缩小
+    let result = send(&mut svm, &relayer, ix_proxy_write(&relayer.pubkey(), pda, b"x".to_vec()));
+    let err = format!("{:?}", result.unwrap_err().err);
+    assert!(err.contains(&format!("Custom({})", u32::from(PxsolError::WrongPayer))), "unexpected error: {err}");
+}
+
+#[test]
+fn test_close_removes_program_writer() {
+    let (mut svm, user) = setup();
+    let pda = user_pda(&user.pubkey());
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    send(&mut svm, &user, ix_register(&user.pubkey())).unwrap();
+    let program_writer = program_writer_pda(&pda);
+    assert_eq!(load_data(&svm, &pda).program_writers, 1);
+
+    // 不传入登记账户不能关闭
+    let result = send(&mut svm, &user, ix_close(&user.pubkey(), &[]));
+    let err = format!("{:?}", result.unwrap_err().err);
+    let expected = format!("Custom({})", u32::from(PxsolError::ProgramWriterStillOpen));
+    assert!(err.contains(&expected), "unexpected error: {err}");
+
+    // 登记账户随记录一起关闭
+    send(&mut svm, &user, ix_close(&user.pubkey(), &[program_writer])).unwrap();
+    assert!(svm.get_account(&program_writer).is_none_or(|account| account.lamports == 0));
+
+    // 同一钱包重新 init 后，调用方程序需要重新登记才能写入
+    send(&mut svm, &user, ix_init(&user.pubkey())).unwrap();
+    let relayer = Keypair::new();
+    svm.airdrop(&relayer.pubkey(), 1_000_000_000).unwrap();
+    let result = send(&mut svm, &relayer, ix_proxy_write(&relayer.pubkey(), pda, b"stale".to_vec()));
+    assert!(result.is_err());
+    assert!(load_data(&svm, &pda).data.is_empty());
+}
@@ -42,7 +42,7 @@ describe("lesson-5-pxsol-ss-anchor", () => {
         // 情况1：账户不存在 → 先创建账户
         console.log("  🆕 账户不存在，正在创建...");
         await program.methods
-          .init()
+          .init(null)
           .accounts({
             user: wallet.publicKey,
             userPda: walletPda,
@@ -56,7 +56,7 @@ describe("lesson-5-pxsol-ss-anchor", () => {
       // 2. 写入或更新数据（无论账户是新创建还是已存在）
       console.log("  💾 正在保存数据...");
       await program.methods
-        .update(data)
+        .update(data, null)
         .accounts({
           user: wallet.publicKey,
           userPda: walletPda,
@@ -189,7 +189,7 @@ describe("lesson-5-pxsol-ss-anchor", () => {
 
     console.log("📝 步骤 1: 手动初始化账户");
     await program.methods
-      .init()
+      .init(null)
       .accounts({
         user: wallet.publicKey,
         userPda: walletPda,
@@ -203,7 +203,7 @@ describe("lesson-5-pxsol-ss-anchor", () => {
 
     console.log("📝 步骤 2: 手动更新（扩容）");
     await program.methods
-      .update(poemEnglish)
+      .update(poemEnglish, null)
       .accounts({
         user: wallet.publicKey,
         userPda: walletPda,
@@ -217,7 +217,7 @@ describe("lesson-5-pxsol-ss-anchor", () => {
 
     console.log("📝 步骤 3: 手动更新（缩小）");
     await program.methods
-      .update(poemChinese)
+      .update(poemChinese, null)
       .accounts({
         user: wallet.publicKey,
         userPda: walletPda,
@@ -229,4 +229,75 @@ describe("lesson-5-pxsol-ss-anchor", () => {
     if (!(await walletPdaData()).equals(poemChinese)) throw new Error("mismatch");
     console.log(`  ✅ 更新成功: "${poemChinese.toString()}"\n`);
   });
+
+  // ============================================================
+  // 测试 3：所有权转移（密钥轮换）
+  // ============================================================
+  it("🔑 所有权转移：transfer_auth 之后新钱包可以更新，旧钱包被拒绝", async () => {
+    console.log("\n📋 测试场景：用户轮换密钥，数据账户跟随新钱包\n");
+
+    const newAuth = anchor.web3.Keypair.generate();
+    await provider.connection.confirmTransaction(
+      await provider.connection.requestAirdrop(
+        newAuth.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      ),
+      "confirmed"
+    );
+
+    console.log("📝 步骤 1: 把所有权转给新钱包");
+    await program.methods
+      .transferAuth(newAuth.publicKey)
+      .accounts({
+        user: wallet.publicKey,
+        userPda: walletPda,
+      })
+      .signers([wallet.payer])
+      .rpc();
+    let account = await program.account.data.fetch(walletPda);
+    if (!account.auth.equals(newAuth.publicKey)) throw new Error("auth 未更新");
+
+    console.log("📝 步骤 2: 新钱包更新数据（PDA 地址不变）");
+    const data = Buffer.from("新钱包写入的数据");
+    await program.methods
+      .update(data, null)
+      .accounts({
+        user: newAuth.publicKey,
+        userPda: walletPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([newAuth])
+      .rpc();
+    if (!(await getData()).equals(data)) throw new Error("数据不匹配");
+
+    console.log("📝 步骤 3: 旧钱包更新应被拒绝");
+    let rejected = false;
+    try {
+      await program.methods
+        .update(Buffer.from("旧钱包"), null)
+        .accounts({
+          user: wallet.publicKey,
+          userPda: walletPda,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers([wallet.payer])
+        .rpc();
+    } catch (error) {
+      rejected = true;
+    }
+    if (!rejected) throw new Error("旧钱包不应再有权限");
+
+    console.log("📝 步骤 4: 把所有权交还给原钱包");
+    await program.methods
+      .transferAuth(wallet.publicKey)
+      .accounts({
+        user: newAuth.publicKey,
+        userPda: walletPda,
+      })
+      .signers([newAuth])
+      .rpc();
+    account = await program.account.data.fetch(walletPda);
+    if (!account.auth.equals(wallet.publicKey)) throw new Error("auth 未交还");
+    console.log("  ✅ 所有权转移测试通过\n");
+  });
 });
@@ -30,10 +30,11 @@ def init():
     rq.account.append(pxsol.core.AccountMeta(data_pubkey, 1))              # 1: user_pda (writable)
     rq.account.append(pxsol.core.AccountMeta(pxsol.program.System.pubkey, 0))  # 2: system_program
     
-    # 构造指令数据：只有方法 discriminator
+    # 构造指令数据：方法 discriminator + 参数
     # [220, 59, 207, 236, 108, 250, 47, 100] = sha256("global:init")[:8]
     rq.data = bytearray().join([
         bytearray([220, 59, 207, 236, 108, 250, 47, 100]),  # init 方法的 discriminator
+        bytearray([0]),                                     # expires_at: None（永不过期）
     ])
     
     # 构造、签名、发送交易
@@ -65,6 +66,7 @@ def update():
         bytearray([219, 200, 88, 176, 158, 63, 253, 127]),  # update 方法的 discriminator
         len(args.args[1].encode()).to_bytes(4, 'little'),   # 数据长度（4字节小端序）
         args.args[1].encode(),                              # 实际数据内容
+        bytearray([0]),                                     # expires_at: None（不修改过期时间）
     ])
     
     # 构造、签名、发送交易
@@ -86,8 +88,8 @@ def load():
     info = pxsol.rpc.get_account_info(data_pubkey.base58(), {})
     
     # 解码并跳过账户头部，只打印实际数据
-    # 账户布局：8 (discriminator) + 32 (auth) + 1 (bump) + 4 (Vec长度) + 数据
-    print(base64.b64decode(info['data'][0])[8 + 32 + 1 + 4:].decode())
+    # 账户布局：8 (discriminator) + 32 (auth) + 1 (bump) + 32 (payer) + 8 (expires_at) + 1 (has_acl) + 4 (program_writers) + 32 (hash) + 4 (Vec长度) + 数据
+    print(base64.b64decode(info['data'][0])[8 + 32 + 1 + 32 + 8 + 1 + 4 + 32 + 4:].decode())
 
 
 if __name__ == '__main__':
@@ -0,0 +1,57 @@
+// 工作区内各程序 LiteSVM 测试共用的辅助函数
+//
+// 各测试文件通过 #[path = "../../../tests/support/litesvm.rs"] mod support; 引入。
+// 找不到 .so 时直接 panic：跳过会让没有编译程序的 CI 也显示测试通过
+
+// 并不是每个测试文件都用到全部函数
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use litesvm::types::TransactionResult;
+use litesvm::LiteSVM;
+use solana_keypair::Keypair;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+/// 工作区 target/deploy 下 .so 文件的路径（各程序都位于 programs/<名称>）
+pub fn deploy_so(name: &str) -> String {
+    format!("{}/../../target/deploy/{name}.so", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// 创建 SVM 并加载程序，返回一个有余额的用户；缺少 .so 时 panic 并提示先运行 anchor build
+pub fn setup(programs: &[(Pubkey, &str)]) -> (LiteSVM, Keypair) {
+    let mut svm = LiteSVM::new();
+    for (program_id, name) in programs {
+        let so = deploy_so(name);
+        assert!(Path::new(&so).exists(), "未找到 {so}，请先运行 anchor build");
+        svm.add_program_from_file(*program_id, &so).unwrap();
+    }
+    let user = Keypair::new();
+    svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+    (svm, user)
+}
+
+/// 发送单条指令的交易，由 signer 支付手续费
+// TransactionResult 是 LiteSVM 定义的类型，Err 分支较大但无法修改
+#[allow(clippy::result_large_err)]
+pub fn send(svm: &mut LiteSVM, signer: &Keypair, ix: Instruction) -> TransactionResult {
+    send_with(svm, &[signer], ix)
+}
+
+/// 发送需要多个签名者的交易，第一个签名者支付手续费
+#[allow(clippy::result_large_err)]
+pub fn send_with(svm: &mut LiteSVM, signers: &[&Keypair], ix: Instruction) -> TransactionResult {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signers[0].pubkey()),
+        signers,
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(tx);
+    // 换一个 blockhash，避免相同指令被当成重复交易
+    svm.expire_blockhash();
+    result
+}
@@ -1,40 +1,274 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token_2022::{self, Token2022, TransferChecked};
-use anchor_spl::token_interface::{Mint, TokenAccount};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use solana_instructions_sysvar::{load_current_index_checked, load_instruction_at_checked};
+use solana_sdk_ids::{ed25519_program, sysvar};
 
 declare_id!("AQkWeJJmQtsXbznwx65CGVZp9dQmyecDXA9f64GvrpVT");
 
-/// Amount to airdrop: 5 tokens with 9 decimals
-const AIRDROP_AMOUNT: u64 = 5_000_000_000;
-const TOKEN_DECIMALS: u8 = 9;
+/// Seed prefix of the pool account, the full seeds are [b"pool", mint]
+pub const POOL_SEED: &[u8] = b"pool";
+/// Seed prefix of the claim receipt, the full seeds are [b"receipt", pool, user]
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+/// Length of the attested message: user (32 bytes) + pool (32 bytes) + amount (u64) + expiry (i64), little endian
+pub const ATTESTATION_SIZE: usize = 32 + 32 + 8 + 8;
 
 #[program]
 pub mod pxsol_spl_anchor {
     use super::*;
 
+    /// Create the airdrop pool of a mint
+    ///
+    /// Only the mint authority can open a pool, and each mint has at most one pool, so one deployed program can run
+    /// independent airdrop campaigns for many mints. The tokens to hand out are held by the mana_spla account.
+    /// The mint can belong to either SPL Token or Token-2022.
+    ///
+    /// With an attestor, every claim must be authorized by an ed25519 signature of that key, see verify_attestation.
+    /// The signer becomes the pool admin, who can pause the pool and hand the role over to another key.
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        amount_per_claim: u64,
+        max_claims: u64,
+        attestor: Option<Pubkey>,
+    ) -> Result<()> {
+        require_gt!(amount_per_claim, 0, ErrorCode::InvalidAmount);
+        require_gt!(max_claims, 0, ErrorCode::InvalidAmount);
+
+        let pool = &mut ctx.accounts.pool;
+        pool.mint = ctx.accounts.mint.key();
+        pool.amount_per_claim = amount_per_claim;
+        pool.max_claims = max_claims;
+        pool.claims = 0;
+        pool.admin = ctx.accounts.admin.key();
+        pool.paused = false;
+        pool.attestor = attestor.unwrap_or_default();
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    /// Stop all claims of the pool, for example while it is being abused
+    pub fn pause(ctx: Context<AdminPool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.paused = true;
+        emit!(PoolPaused { pool: pool.key(), admin: pool.admin });
+        Ok(())
+    }
+
+    /// Resume claims of a paused pool
+    pub fn unpause(ctx: Context<AdminPool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.paused = false;
+        emit!(PoolUnpaused { pool: pool.key(), admin: pool.admin });
+        Ok(())
+    }
+
+    /// Hand the pool admin role over to new_admin, the current admin loses it immediately
+    pub fn set_admin(ctx: Context<AdminPool>, new_admin: Pubkey) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let old_admin = pool.admin;
+        pool.admin = new_admin;
+        emit!(PoolAdminChanged { pool: pool.key(), old_admin, new_admin });
+        Ok(())
+    }
+
+    /// Replace the attestor whose signature gates claims, None removes the gate and lets every claim through
+    ///
+    /// Use this to rotate a leaked attestor key without reopening the pool.
+    pub fn set_attestor(ctx: Context<AdminPool>, new_attestor: Option<Pubkey>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let old_attestor = pool.attestor;
+        pool.attestor = new_attestor.unwrap_or_default();
+        emit!(PoolAttestorChanged { pool: pool.key(), old_attestor, new_attestor: pool.attestor });
+        Ok(())
+    }
+
     /// Airdrop SPL tokens to a user
     ///
     /// Anyone can call this instruction to receive tokens from the airdrop pool.
-    /// The user must pay the transaction fee but no other permissions are required.
+    /// The user pays the transaction fee and the rent of the claim receipt but no other permissions are required.
+    /// Each user can claim once per pool: a second call fails when the receipt account is created again.
+    ///
+    /// Deprecated: the account list still contains the program itself as `mana`. It is kept so that existing clients
+    /// keep working during the transition, new clients should call airdrop_v2.
     pub fn airdrop(ctx: Context<Airdrop>) -> Result<()> {
-        let signer_seeds_flat = &[&[][..], &[ctx.bumps.mana_auth]];
-        let signer_seeds = &[&signer_seeds_flat[..]];
-
-        let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_account = TransferChecked {
-            from: ctx.accounts.mana_spla.to_account_info(),
-            mint: ctx.accounts.mint.to_account_info(),
-            to: ctx.accounts.user_spla.to_account_info(),
-            authority: ctx.accounts.mana_auth.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_account, signer_seeds);
-        token_2022::transfer_checked(cpi_ctx, AIRDROP_AMOUNT, TOKEN_DECIMALS)?;
-
-        Ok(()) 
+        let accounts = ctx.accounts;
+        verify_attestation(&accounts.pool, &accounts.user, accounts.instructions.as_ref())?;
+        pay_out(
+            &accounts.user,
+            &accounts.user_spla,
+            &accounts.mana_auth,
+            ctx.bumps.mana_auth,
+            &accounts.mana_spla,
+            &mut accounts.pool,
+            &mut accounts.receipt,
+            ctx.bumps.receipt,
+            &accounts.mint,
+            &accounts.token_program,
+        )
+    }
+
+    /// Airdrop SPL tokens to a user, same as airdrop but without the `mana` program account
+    pub fn airdrop_v2(ctx: Context<AirdropV2>) -> Result<()> {
+        let accounts = ctx.accounts;
+        verify_attestation(&accounts.pool, &accounts.user, accounts.instructions.as_ref())?;
+        pay_out(
+            &accounts.user,
+            &accounts.user_spla,
+            &accounts.mana_auth,
+            ctx.bumps.mana_auth,
+            &accounts.mana_spla,
+            &mut accounts.pool,
+            &mut accounts.receipt,
+            ctx.bumps.receipt,
+            &accounts.mint,
+            &accounts.token_program,
+        )
     }
 }
 
+/// Check that the pool's attestor authorized this claim, pools without an attestor accept every claim
+///
+/// The instruction right before the current one must be an Ed25519 program instruction carrying one signature of the
+/// attestor over user || pool || amount_per_claim || expiry, with the signature, public key and message all inside
+/// that instruction's data. The Ed25519 program has already verified the signature when this instruction runs, so
+/// only its content is compared here.
+fn verify_attestation(pool: &Account<Pool>, user: &Signer, instructions: Option<&UncheckedAccount>) -> Result<()> {
+    if pool.attestor == Pubkey::default() {
+        return Ok(());
+    }
+    let instructions = instructions.ok_or(ErrorCode::MissingAttestation)?;
+    let current = load_current_index_checked(instructions)?;
+    require_gt!(current, 0, ErrorCode::MissingAttestation);
+    let ix = load_instruction_at_checked(current as usize - 1, instructions)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::MissingAttestation);
+
+    // Ed25519 instruction data: signature count (u8), padding (u8), then for each signature the offsets of
+    // signature, public key and message (u16 little endian, each paired with an instruction index)
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, ErrorCode::InvalidAttestation);
+    let read = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let (public_key_offset, message_offset, message_size) = (read(6) as usize, read(10) as usize, read(12) as usize);
+    // u16::MAX refers to the Ed25519 instruction itself, anything else could point the check at another instruction
+    require!(
+        read(4) == u16::MAX && read(8) == u16::MAX && read(14) == u16::MAX,
+        ErrorCode::InvalidAttestation
+    );
+    let public_key = data.get(public_key_offset..public_key_offset + 32).ok_or(ErrorCode::InvalidAttestation)?;
+    let message = data.get(message_offset..message_offset + message_size).ok_or(ErrorCode::InvalidAttestation)?;
+    require!(public_key == pool.attestor.as_ref(), ErrorCode::InvalidAttestation);
+    require!(message.len() == ATTESTATION_SIZE, ErrorCode::InvalidAttestation);
+    require!(message[0..32] == user.key().to_bytes(), ErrorCode::InvalidAttestation);
+    require!(message[32..64] == pool.key().to_bytes(), ErrorCode::InvalidAttestation);
+    require!(message[64..72] == pool.amount_per_claim.to_le_bytes(), ErrorCode::InvalidAttestation);
+    let expiry = i64::from_le_bytes(message[72..80].try_into().unwrap());
+    require_gt!(expiry, Clock::get()?.unix_timestamp, ErrorCode::AttestationExpired);
+    Ok(())
+}
+
+/// Count the claim against the pool, fill in the receipt and transfer amount_per_claim from mana_spla to user_spla
+#[allow(clippy::too_many_arguments)]
+fn pay_out<'info>(
+    user: &Signer<'info>,
+    user_spla: &InterfaceAccount<'info, TokenAccount>,
+    mana_auth: &SystemAccount<'info>,
+    mana_auth_bump: u8,
+    mana_spla: &InterfaceAccount<'info, TokenAccount>,
+    pool: &mut Account<'info, Pool>,
+    receipt: &mut Account<'info, ClaimReceipt>,
+    receipt_bump: u8,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    require_gt!(pool.max_claims, pool.claims, ErrorCode::PoolExhausted);
+    pool.claims += 1;
+
+    receipt.pool = pool.key();
+    receipt.user = user.key();
+    receipt.amount = pool.amount_per_claim;
+    receipt.claimed_at = Clock::get()?.unix_timestamp;
+    receipt.bump = receipt_bump;
+
+    let signer_seeds_flat = &[&[][..], &[mana_auth_bump]];
+    let signer_seeds = &[&signer_seeds_flat[..]];
+
+    let cpi_program = token_program.to_account_info();
+    let cpi_account = TransferChecked {
+        from: mana_spla.to_account_info(),
+        mint: mint.to_account_info(),
+        to: user_spla.to_account_info(),
+        authority: mana_auth.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_account, signer_seeds);
+    token_interface::transfer_checked(cpi_ctx, pool.amount_per_claim, mint.decimals)?;
+
+    Ok(())
+}
+
+/// Airdrop campaign parameters of a mint
+#[account]
+#[derive(InitSpace)]
+pub struct Pool {
+    /// The mint handed out by this pool
+    pub mint: Pubkey,
+    /// Amount sent on each claim, in base units
+    pub amount_per_claim: u64,
+    /// Total number of claims the pool pays out
+    pub max_claims: u64,
+    /// Number of claims paid out so far
+    pub claims: u64,
+    /// Key allowed to pause the pool and change the admin or the attestor
+    pub admin: Pubkey,
+    /// Claims are rejected while the pool is paused
+    pub paused: bool,
+    /// Key whose ed25519 signature is required for each claim, the default pubkey means claims are not gated
+    pub attestor: Pubkey,
+    pub bump: u8,
+}
+
+/// Record of a paid out claim, kept for auditing
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimReceipt {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    /// Amount sent to the user, in base units
+    pub amount: u64,
+    /// Unix timestamp of the claim
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
+#[derive(Accounts)]
+pub struct InitializePool<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Pool::INIT_SPACE,
+        seeds = [POOL_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        constraint = mint.mint_authority == Some(admin.key()).into() @ ErrorCode::Unauthorized,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts of the pool admin instructions: pause, unpause, set_admin and set_attestor
+#[derive(Accounts)]
+pub struct AdminPool<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        has_one = admin @ ErrorCode::NotAdmin,
+    )]
+    pub pool: Account<'info, Pool>,
+}
+
+/// Accounts of the deprecated airdrop instruction, AirdropV2 plus the `mana` program account
 #[derive(Accounts)]
 pub struct Airdrop<'info> {
     #[account(mut)]
@@ -54,7 +288,6 @@ pub struct Airdrop<'info> {
     #[account(
         seeds = [b""],
         bump,
-        seeds::program = mana.key(),
     )]
     pub mana_auth: SystemAccount<'info>,
     #[account(
@@ -64,14 +297,134 @@ pub struct Airdrop<'info> {
         associated_token::token_program = token_program,
     )]
     pub mana_spla: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [POOL_SEED, mint.key().as_ref()],
+        bump = pool.bump,
+        has_one = mint,
+        constraint = !pool.paused @ ErrorCode::Paused,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ClaimReceipt::INIT_SPACE,
+        seeds = [RECEIPT_SEED, pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub receipt: Account<'info, ClaimReceipt>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    /// Instructions sysvar, only needed when the pool has an attestor
+    /// CHECK: address is checked, the content is read by verify_attestation
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+/// Accounts of airdrop_v2, the mana_auth PDA is derived from this program's ID
+#[derive(Accounts)]
+pub struct AirdropV2<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_spla: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b""],
+        bump,
+    )]
+    pub mana_auth: SystemAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = mana_auth,
+        associated_token::token_program = token_program,
+    )]
+    pub mana_spla: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [POOL_SEED, mint.key().as_ref()],
+        bump = pool.bump,
+        has_one = mint,
+        constraint = !pool.paused @ ErrorCode::Paused,
+    )]
+    pub pool: Account<'info, Pool>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + ClaimReceipt::INIT_SPACE,
+        seeds = [RECEIPT_SEED, pool.key().as_ref(), user.key().as_ref()],
+        bump,
+    )]
+    pub receipt: Account<'info, ClaimReceipt>,
+    #[account(
+        mint::token_program = token_program,
+    )]
     pub mint: InterfaceAccount<'info, Mint>,
-    pub token_program: Program<'info, Token2022>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+    /// Instructions sysvar, only needed when the pool has an attestor
+    /// CHECK: address is checked, the content is read by verify_attestation
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions: Option<UncheckedAccount<'info>>,
+}
+
+#[event]
+pub struct PoolPaused {
+    pub pool: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct PoolUnpaused {
+    pub pool: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct PoolAdminChanged {
+    pub pool: Pubkey,
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+/// Emitted by set_attestor, the default pubkey means claims are not gated
+#[event]
+pub struct PoolAttestorChanged {
+    pub pool: Pubkey,
+    pub old_attestor: Pubkey,
+    pub new_attestor: Pubkey,
 }
 
 #[error_code]
 pub enum ErrorCode {
     #[msg("Invalid mana program")]
     InvalidManaProgram,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Signer is not the mint authority")]
+    Unauthorized,
+    #[msg("All claims of the pool have been paid out")]
+    PoolExhausted,
+    #[msg("Claim requires an ed25519 attestation instruction right before it")]
+    MissingAttestation,
+    #[msg("Attestation does not match the attestor, user, pool or amount")]
+    InvalidAttestation,
+    #[msg("Attestation has expired")]
+    AttestationExpired,
+    #[msg("Signer is not the pool admin")]
+    NotAdmin,
+    #[msg("Pool is paused")]
+    Paused,
 }
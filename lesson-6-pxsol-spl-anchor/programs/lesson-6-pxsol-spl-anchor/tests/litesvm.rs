@@ -0,0 +1,547 @@
+// Load the compiled program into an in-process LiteSVM, no local validator or network is needed
+//
+// Usage:
+//   anchor build      # produces target/deploy/pxsol_spl_anchor.so
+//   cargo test
+//
+// Without anchor build every test fails with a hint, so a CI job that forgot to build cannot pass silently
+
+use std::path::Path;
+
+use anchor_lang::prelude::{Clock, Pubkey};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::{system_program, AccountDeserialize, Event, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address_with_program_id};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_interface::TokenAccount;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use litesvm::types::{TransactionMetadata, TransactionResult};
+use litesvm::LiteSVM;
+use pxsol_spl_anchor::{
+    accounts, instruction, ClaimReceipt, ErrorCode, Pool, PoolAdminChanged, PoolAttestorChanged, PoolPaused,
+    PoolUnpaused, ID, POOL_SEED, RECEIPT_SEED,
+};
+use solana_keypair::Keypair;
+use solana_sdk_ids::{ed25519_program, sysvar};
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+const PROGRAM_SO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy/pxsol_spl_anchor.so");
+
+/// Amount sent on each claim: 5 tokens with 9 decimals
+const AMOUNT: u64 = 5_000_000_000;
+/// Amount minted into mana_spla before the tests
+const SUPPLY: u64 = 100_000_000_000;
+
+// ============================================
+// Test helpers
+// ============================================
+
+/// SVM with the program loaded, the mint authority (who opens the pool) and a mint owned by token_program
+struct Env {
+    svm: LiteSVM,
+    admin: Keypair,
+    mint: Pubkey,
+    token_program: Pubkey,
+}
+
+/// Create a Token-2022 mint, fund mana_spla and open a pool paying AMOUNT per claim for at most max_claims claims
+fn setup(max_claims: u64) -> Env {
+    setup_with(spl_token_2022::ID, max_claims, None)
+}
+
+/// Same as setup, but the mint is owned by token_program and claims may be gated by an attestor
+fn setup_with(token_program: Pubkey, max_claims: u64, attestor: Option<Pubkey>) -> Env {
+    assert!(Path::new(PROGRAM_SO).exists(), "{PROGRAM_SO} not found, run anchor build first");
+    let mut svm = LiteSVM::new();
+    svm.add_program_from_file(ID, PROGRAM_SO).unwrap();
+    let admin = Keypair::new();
+    svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
+
+    let mint = create_mint(&mut svm, &admin, &token_program);
+    let mut env = Env { svm, admin, mint, token_program };
+    let mana_auth = mana_auth();
+    let ixs = [
+        associated_token::spl_associated_token_account::instruction::create_associated_token_account(
+            &env.admin.pubkey(),
+            &mana_auth,
+            &env.mint,
+            &token_program,
+        ),
+        spl_token_2022::instruction::mint_to(
+            &token_program,
+            &env.mint,
+            &env.ata(&mana_auth),
+            &env.admin.pubkey(),
+            &[],
+            SUPPLY,
+        )
+        .unwrap(),
+        ix_initialize_pool(&env.admin.pubkey(), &env.mint, AMOUNT, max_claims, attestor),
+    ];
+    send(&mut env.svm, &[&env.admin], &ixs).unwrap();
+    env
+}
+
+/// Create a mint with 9 decimals, the instruction builders of spl_token_2022 also accept the SPL Token program id
+fn create_mint(svm: &mut LiteSVM, authority: &Keypair, token_program: &Pubkey) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = svm.minimum_balance_for_rent_exemption(spl_token_2022::state::Mint::LEN);
+    let ixs = [
+        system_instruction::create_account(
+            &authority.pubkey(),
+            &mint.pubkey(),
+            rent,
+            spl_token_2022::state::Mint::LEN as u64,
+            token_program,
+        ),
+        spl_token_2022::instruction::initialize_mint2(token_program, &mint.pubkey(), &authority.pubkey(), None, 9)
+            .unwrap(),
+    ];
+    send(svm, &[authority, &mint], &ixs).unwrap();
+    mint.pubkey()
+}
+
+/// Send the instructions in one transaction, the first signer pays the fee
+// The Err variant of LiteSVM's TransactionResult is large, but the type is not ours to box
+#[allow(clippy::result_large_err)]
+fn send(svm: &mut LiteSVM, signers: &[&Keypair], ixs: &[Instruction]) -> TransactionResult {
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&signers[0].pubkey()), signers, svm.latest_blockhash());
+    let result = svm.send_transaction(tx);
+    // Use a new blockhash so that the same instruction is not rejected as a duplicate transaction
+    svm.expire_blockhash();
+    result
+}
+
+fn pool(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[POOL_SEED, mint.as_ref()], &ID).0
+}
+
+fn ix_initialize_pool(
+    admin: &Pubkey,
+    mint: &Pubkey,
+    amount_per_claim: u64,
+    max_claims: u64,
+    attestor: Option<Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts::InitializePool {
+            admin: *admin,
+            pool: pool(mint),
+            mint: *mint,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+        data: instruction::InitializePool { amount_per_claim, max_claims, attestor }.data(),
+    }
+}
+
+fn mana_auth() -> Pubkey {
+    Pubkey::find_program_address(&[b""], &ID).0
+}
+
+/// Ed25519 program instruction carrying the signature of signer over message, all offsets point into its own data
+fn ix_ed25519(signer: &Keypair, message: &[u8]) -> Instruction {
+    let signature = signer.sign_message(message);
+    let (public_key_offset, signature_offset, message_offset) = (16u16, 48u16, 112u16);
+    let mut data = vec![1, 0];
+    for value in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(signer.pubkey().as_ref());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(message);
+    Instruction { program_id: ed25519_program::ID, accounts: vec![], data }
+}
+
+fn assert_error(result: TransactionResult, code: ErrorCode) {
+    let err = format!("{:?}", result.unwrap_err().err);
+    assert!(err.contains(&format!("Custom({})", u32::from(code))), "unexpected error: {err}");
+}
+
+/// emit! logs the event as "Program data: " followed by base64(discriminator || borsh fields)
+fn assert_event(meta: &TransactionMetadata, event: impl Event) {
+    let expected = format!("Program data: {}", STANDARD.encode(event.data()));
+    assert!(meta.logs.iter().any(|log| log == &expected), "{expected} not in logs: {:#?}", meta.logs);
+}
+
+impl Env {
+    fn ata(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &self.token_program)
+    }
+
+    fn pool(&self) -> Pubkey {
+        pool(&self.mint)
+    }
+
+    fn receipt(&self, user: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[RECEIPT_SEED, self.pool().as_ref(), user.as_ref()], &ID).0
+    }
+
+    /// Create a user with enough SOL for the ATA and receipt rent
+    fn user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), 1_000_000_000).unwrap();
+        user
+    }
+
+    /// Deprecated account list, the program itself is passed as mana
+    fn ix_airdrop(&self, user: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: accounts::Airdrop {
+                user: *user,
+                user_spla: self.ata(user),
+                mana: ID,
+                mana_auth: mana_auth(),
+                mana_spla: self.ata(&mana_auth()),
+                pool: self.pool(),
+                receipt: self.receipt(user),
+                mint: self.mint,
+                token_program: self.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+                instructions: None,
+            }
+            .to_account_metas(None),
+            data: instruction::Airdrop {}.data(),
+        }
+    }
+
+    fn ix_airdrop_v2(&self, user: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: accounts::AirdropV2 {
+                user: *user,
+                user_spla: self.ata(user),
+                mana_auth: mana_auth(),
+                mana_spla: self.ata(&mana_auth()),
+                pool: self.pool(),
+                receipt: self.receipt(user),
+                mint: self.mint,
+                token_program: self.token_program,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+                instructions: Some(sysvar::instructions::ID),
+            }
+            .to_account_metas(None),
+            data: instruction::AirdropV2 {}.data(),
+        }
+    }
+
+    /// Message the attestor signs: user || pool || amount || expiry
+    fn attestation(&self, user: &Pubkey, expiry: i64) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend_from_slice(user.as_ref());
+        message.extend_from_slice(self.pool().as_ref());
+        message.extend_from_slice(&AMOUNT.to_le_bytes());
+        message.extend_from_slice(&expiry.to_le_bytes());
+        message
+    }
+
+    fn now(&self) -> i64 {
+        self.svm.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    /// pause, unpause, set_admin or set_attestor, signed by admin
+    fn ix_admin(&self, admin: &Pubkey, data: Vec<u8>) -> Instruction {
+        Instruction {
+            program_id: ID,
+            accounts: accounts::AdminPool { admin: *admin, pool: self.pool() }.to_account_metas(None),
+            data,
+        }
+    }
+
+    fn balance(&self, account: &Pubkey) -> u64 {
+        let account = self.svm.get_account(account).unwrap();
+        TokenAccount::try_deserialize(&mut account.data.as_slice()).unwrap().amount
+    }
+
+    fn load_pool(&self) -> Pool {
+        let account = self.svm.get_account(&self.pool()).unwrap();
+        Pool::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    fn load_receipt(&self, user: &Pubkey) -> ClaimReceipt {
+        let account = self.svm.get_account(&self.receipt(user)).unwrap();
+        ClaimReceipt::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+}
+
+// ============================================
+// Test cases
+// ============================================
+
+#[test]
+fn test_initialize_pool_requires_mint_authority() {
+    let mut env = setup(10);
+    let pool = env.load_pool();
+    assert_eq!(pool.mint, env.mint);
+    assert_eq!(pool.amount_per_claim, AMOUNT);
+    assert_eq!(pool.max_claims, 10);
+    assert_eq!(pool.claims, 0);
+    assert_eq!(pool.admin, env.admin.pubkey());
+    assert!(!pool.paused);
+
+    // Only the mint authority can open a pool
+    let mint = create_mint(&mut env.svm, &env.admin, &spl_token_2022::ID);
+    let other = env.user();
+    let ix = ix_initialize_pool(&other.pubkey(), &mint, AMOUNT, 10, None);
+    assert_error(send(&mut env.svm, &[&other], &[ix]), ErrorCode::Unauthorized);
+
+    // The pool of a mint can only be opened once
+    let ix = ix_initialize_pool(&env.admin.pubkey(), &env.mint, AMOUNT, 10, None);
+    assert!(send(&mut env.svm, &[&env.admin], &[ix]).is_err());
+}
+
+#[test]
+fn test_airdrop_v2_accounts() {
+    let mut env = setup(10);
+    let user = env.user();
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+    assert_eq!(env.balance(&env.ata(&mana_auth())), SUPPLY - AMOUNT);
+    let receipt = env.load_receipt(&user.pubkey());
+    assert_eq!(receipt.pool, env.pool());
+    assert_eq!(receipt.user, user.pubkey());
+    assert_eq!(receipt.amount, AMOUNT);
+    assert_eq!(env.load_pool().claims, 1);
+}
+
+#[test]
+fn test_airdrop_deprecated_accounts() {
+    // Clients that still pass the program as mana keep working during the transition
+    let mut env = setup(10);
+    let user = env.user();
+    let ix = env.ix_airdrop(&user.pubkey());
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+
+    // Clients built before the optional instructions account was added leave it out entirely
+    let user = env.user();
+    let mut ix = env.ix_airdrop(&user.pubkey());
+    ix.accounts.pop();
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+
+    // Any other program in the mana slot is rejected
+    let other = env.user();
+    let mut ix = env.ix_airdrop(&other.pubkey());
+    ix.accounts[2].pubkey = system_program::ID;
+    assert!(send(&mut env.svm, &[&other], &[ix]).is_err());
+}
+
+#[test]
+fn test_airdrop_once_per_user() {
+    let mut env = setup(10);
+    let user = env.user();
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+
+    // The receipt already exists, so both instruction versions fail to create it again
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    assert!(send(&mut env.svm, &[&user], &[ix]).is_err());
+    let ix = env.ix_airdrop(&user.pubkey());
+    assert!(send(&mut env.svm, &[&user], &[ix]).is_err());
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+}
+
+#[test]
+fn test_airdrop_pool_exhausted() {
+    let mut env = setup(1);
+    let user = env.user();
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+
+    let other = env.user();
+    let ix = env.ix_airdrop_v2(&other.pubkey());
+    assert_error(send(&mut env.svm, &[&other], &[ix]), ErrorCode::PoolExhausted);
+}
+
+#[test]
+fn test_airdrop_spl_token() {
+    // Mints of the classic SPL Token program use the same instructions
+    let mut env = setup_with(spl_token::ID, 10, None);
+    let user = env.user();
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+    assert_eq!(env.balance(&env.ata(&mana_auth())), SUPPLY - AMOUNT);
+
+    let other = env.user();
+    let ix = env.ix_airdrop(&other.pubkey());
+    send(&mut env.svm, &[&other], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&other.pubkey())), AMOUNT);
+}
+
+#[test]
+fn test_airdrop_wrong_token_program() {
+    // The token program must be the owner of the mint
+    let mut env = setup_with(spl_token::ID, 10, None);
+    let user = env.user();
+    let mut ix = env.ix_airdrop_v2(&user.pubkey());
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == spl_token::ID) {
+        meta.pubkey = spl_token_2022::ID;
+    }
+    assert!(send(&mut env.svm, &[&user], &[ix]).is_err());
+}
+
+#[test]
+fn test_airdrop_attested() {
+    let attestor = Keypair::new();
+    let mut env = setup_with(spl_token_2022::ID, 10, Some(attestor.pubkey()));
+    assert_eq!(env.load_pool().attestor, attestor.pubkey());
+    let user = env.user();
+    let message = env.attestation(&user.pubkey(), env.now() + 60);
+    let ixs = [ix_ed25519(&attestor, &message), env.ix_airdrop_v2(&user.pubkey())];
+    send(&mut env.svm, &[&user], &ixs).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+
+    // The deprecated instruction is gated the same way once it passes the instructions sysvar
+    let user = env.user();
+    let message = env.attestation(&user.pubkey(), env.now() + 60);
+    let mut ix = env.ix_airdrop(&user.pubkey());
+    ix.accounts.last_mut().unwrap().pubkey = sysvar::instructions::ID;
+    send(&mut env.svm, &[&user], &[ix_ed25519(&attestor, &message), ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+}
+
+#[test]
+fn test_airdrop_attestation_missing() {
+    let attestor = Keypair::new();
+    let mut env = setup_with(spl_token_2022::ID, 10, Some(attestor.pubkey()));
+    let user = env.user();
+
+    // No Ed25519 instruction before the claim
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    assert_error(send(&mut env.svm, &[&user], &[ix]), ErrorCode::MissingAttestation);
+
+    // No instructions sysvar, as sent by older clients
+    let ix = env.ix_airdrop(&user.pubkey());
+    assert_error(send(&mut env.svm, &[&user], &[ix]), ErrorCode::MissingAttestation);
+}
+
+#[test]
+fn test_airdrop_attestation_rejected() {
+    let attestor = Keypair::new();
+    let mut env = setup_with(spl_token_2022::ID, 10, Some(attestor.pubkey()));
+    let user = env.user();
+    let expiry = env.now() + 60;
+
+    // Signed by a key other than the attestor
+    let message = env.attestation(&user.pubkey(), expiry);
+    let ixs = [ix_ed25519(&Keypair::new(), &message), env.ix_airdrop_v2(&user.pubkey())];
+    assert_error(send(&mut env.svm, &[&user], &ixs), ErrorCode::InvalidAttestation);
+
+    // Issued to another user
+    let message = env.attestation(&Keypair::new().pubkey(), expiry);
+    let ixs = [ix_ed25519(&attestor, &message), env.ix_airdrop_v2(&user.pubkey())];
+    assert_error(send(&mut env.svm, &[&user], &ixs), ErrorCode::InvalidAttestation);
+
+    // Expired
+    let message = env.attestation(&user.pubkey(), env.now());
+    let ixs = [ix_ed25519(&attestor, &message), env.ix_airdrop_v2(&user.pubkey())];
+    assert_error(send(&mut env.svm, &[&user], &ixs), ErrorCode::AttestationExpired);
+    assert!(env.svm.get_account(&env.receipt(&user.pubkey())).is_none());
+}
+
+#[test]
+fn test_pause_and_unpause() {
+    let mut env = setup(10);
+    let user = env.user();
+
+    // Only the admin can pause
+    let ix = env.ix_admin(&user.pubkey(), instruction::Pause {}.data());
+    assert_error(send(&mut env.svm, &[&user], &[ix]), ErrorCode::NotAdmin);
+
+    let ix = env.ix_admin(&env.admin.pubkey(), instruction::Pause {}.data());
+    let meta = send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    assert!(env.load_pool().paused);
+    assert_event(&meta, PoolPaused { pool: env.pool(), admin: env.admin.pubkey() });
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    assert_error(send(&mut env.svm, &[&user], &[ix]), ErrorCode::Paused);
+    let ix = env.ix_airdrop(&user.pubkey());
+    assert_error(send(&mut env.svm, &[&user], &[ix]), ErrorCode::Paused);
+
+    let ix = env.ix_admin(&env.admin.pubkey(), instruction::Unpause {}.data());
+    let meta = send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    assert!(!env.load_pool().paused);
+    assert_event(&meta, PoolUnpaused { pool: env.pool(), admin: env.admin.pubkey() });
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+}
+
+#[test]
+fn test_set_admin() {
+    let mut env = setup(10);
+    let new_admin = env.user();
+    let ix = env.ix_admin(&env.admin.pubkey(), instruction::SetAdmin { new_admin: new_admin.pubkey() }.data());
+    let meta = send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    assert_eq!(env.load_pool().admin, new_admin.pubkey());
+    assert_event(
+        &meta,
+        PoolAdminChanged { pool: env.pool(), old_admin: env.admin.pubkey(), new_admin: new_admin.pubkey() },
+    );
+
+    // The old admin lost the role, the new one has it
+    let ix = env.ix_admin(&env.admin.pubkey(), instruction::Pause {}.data());
+    assert_error(send(&mut env.svm, &[&env.admin], &[ix]), ErrorCode::NotAdmin);
+    let ix = env.ix_admin(&new_admin.pubkey(), instruction::Pause {}.data());
+    send(&mut env.svm, &[&new_admin], &[ix]).unwrap();
+    assert!(env.load_pool().paused);
+}
+
+#[test]
+fn test_set_attestor() {
+    let mut env = setup(10);
+    let attestor = Keypair::new();
+    let user = env.user();
+
+    // Only the admin can change the attestor
+    let data = instruction::SetAttestor { new_attestor: Some(attestor.pubkey()) }.data();
+    let ix = env.ix_admin(&user.pubkey(), data.clone());
+    assert_error(send(&mut env.svm, &[&user], &[ix]), ErrorCode::NotAdmin);
+
+    // Once set, claims need the attestor's signature
+    let ix = env.ix_admin(&env.admin.pubkey(), data);
+    let meta = send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    assert_eq!(env.load_pool().attestor, attestor.pubkey());
+    assert_event(
+        &meta,
+        PoolAttestorChanged { pool: env.pool(), old_attestor: Pubkey::default(), new_attestor: attestor.pubkey() },
+    );
+    let ix = env.ix_airdrop_v2(&user.pubkey());
+    assert_error(send(&mut env.svm, &[&user], &[ix]), ErrorCode::MissingAttestation);
+    let message = env.attestation(&user.pubkey(), env.now() + 60);
+    let ixs = [ix_ed25519(&attestor, &message), env.ix_airdrop_v2(&user.pubkey())];
+    send(&mut env.svm, &[&user], &ixs).unwrap();
+
+    // Removing the attestor lets claims through without a signature again
+    let ix = env.ix_admin(&env.admin.pubkey(), instruction::SetAttestor { new_attestor: None }.data());
+    let meta = send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    assert_eq!(env.load_pool().attestor, Pubkey::default());
+    assert_event(
+        &meta,
+        PoolAttestorChanged { pool: env.pool(), old_attestor: attestor.pubkey(), new_attestor: Pubkey::default() },
+    );
+    let other = env.user();
+    let ix = env.ix_airdrop_v2(&other.pubkey());
+    send(&mut env.svm, &[&other], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&other.pubkey())), AMOUNT);
+}
@@ -1,5 +1,6 @@
 import argparse
 import base64
+import hashlib
 import json
 import pxsol
 import random
@@ -70,6 +71,67 @@ def deploy():
     pubkey_mana_auth = pubkey_mana.derive_pda(pubkey_mana_seed)[0]
     user.spl_transfer(pubkey_mint, pubkey_mana_auth, 100000000 * 10**9)
 
+    # Config airdrop: 5 tokens per claim, at most once per 9000 slots (about 1 hour), 50 tokens per wallet
+    config_send(user, pubkey_mana, pubkey_mint, 5 * 10**9, 9000, 50 * 10**9, 0, 0, False)
+
+
+def spl_program(pubkey_mint: pxsol.core.PubKey) -> pxsol.core.PubKey:
+    # 铸造账户的所有者即为代币程序（SPL Token 或 Token-2022）
+    info = pxsol.rpc.get_account_info(pubkey_mint.base58(), {})
+    return pxsol.core.PubKey.base58_decode(info['owner'])
+
+
+def spl_account(pubkey_owner: pxsol.core.PubKey, pubkey_mint: pxsol.core.PubKey, pubkey_spl: pxsol.core.PubKey) -> pxsol.core.PubKey:
+    # 关联代币账户地址 = PDA([owner, token program, mint], ATA 程序)
+    return pxsol.program.AssociatedTokenAccount.pubkey.derive_pda(pubkey_owner.p + pubkey_spl.p + pubkey_mint.p)[0]
+
+
+def config_send(
+    user: pxsol.wallet.Wallet,
+    pubkey_mana: pxsol.core.PubKey,
+    pubkey_mint: pxsol.core.PubKey,
+    amount: int,
+    cooldown: int,
+    cap: int,
+    cliff: int,
+    duration: int,
+    vesting: bool,
+):
+    pubkey_config = pubkey_mana.derive_pda(bytearray(b'config') + pubkey_mint.p)[0]  # 配置账户 PDA
+    rq = pxsol.core.Requisition(pubkey_mana, [], bytearray())
+    rq.account.append(pxsol.core.AccountMeta(user.pubkey, 3))  # [0] 管理员账户（签名者 + 可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_config, 1))  # [1] 配置账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mint, 0))  # [2] 代币 Mint（只读）
+    rq.account.append(pxsol.core.AccountMeta(pxsol.program.System.pubkey, 0))  # [3] 系统程序
+    rq.data = bytearray([0x01])  # 指令类型
+    rq.data.extend(amount.to_bytes(8, 'big'))  # 每次空投数量（大端序）
+    rq.data.extend(cooldown.to_bytes(8, 'big'))  # 冷却 slot 数（大端序，0 表示不限制）
+    rq.data.extend(cap.to_bytes(8, 'big'))  # 每个钱包累计上限（大端序，0 表示不限制）
+    rq.data.extend(cliff.to_bytes(8, 'big', signed=True))  # 归属模式锁仓期（秒）
+    rq.data.extend(duration.to_bytes(8, 'big', signed=True))  # 归属模式线性释放时长（秒）
+    rq.data.append(1 if vesting else 0)  # 模式：0 直接空投（0x00），1 归属模式（0x07）
+    tx = pxsol.core.Transaction.requisition_decode(user.pubkey, [rq])
+    tx.message.recent_blockhash = pxsol.base58.decode(pxsol.rpc.get_latest_blockhash({})['blockhash'])
+    tx.sign([user.prikey])
+    pxsol.log.debugln(f'main: config amount={amount} cooldown={cooldown} cap={cap} cliff={cliff} duration={duration} vesting={vesting}')
+    txid = pxsol.rpc.send_transaction(base64.b64encode(tx.serialize()).decode(), {})
+    pxsol.rpc.wait([txid])
+
+
+def config():
+    # Update airdrop config, e.g. python make.py --prikey ADMIN config AMOUNT [COOLDOWN] [CAP] [CLIFF DURATION]
+    # 给出 CLIFF 和 DURATION 时切换到归属模式，否则为直接空投模式
+    user = pxsol.wallet.Wallet(pxsol.core.PriKey.base58_decode(args.prikey))
+    pubkey_mint = pxsol.core.PubKey.base58_decode(info_load('pubkey_mint'))
+    pubkey_mana = pxsol.core.PubKey.base58_decode(info_load('pubkey_mana'))
+    amount = int(args.args[1])
+    cooldown = int(args.args[2]) if len(args.args) > 2 else 0
+    cap = int(args.args[3]) if len(args.args) > 3 else 0
+    cliff = int(args.args[4]) if len(args.args) > 4 else 0
+    duration = int(args.args[5]) if len(args.args) > 5 else 0
+    vesting = len(args.args) > 4
+    config_send(user, pubkey_mana, pubkey_mint, amount, cooldown, cap, cliff, duration, vesting)
+
 
 def update():
     # Update spl mana
@@ -93,29 +155,196 @@ def genuser():
     pxsol.log.debugln(f'main: request sol airdrop done')
 
 
+def treasury_send(data: bytearray):
+    # 提取或关闭国库：代币转入管理员自己的关联代币账户，关闭时 Mana 的关联代币账户和配置账户的租金退还给管理员
+    user = pxsol.wallet.Wallet(pxsol.core.PriKey.base58_decode(args.prikey))
+    pubkey_mint = pxsol.core.PubKey.base58_decode(info_load('pubkey_mint'))
+    pubkey_spl = spl_program(pubkey_mint)  # 铸造账户所属的代币程序
+    pubkey_mana = pxsol.core.PubKey.base58_decode(info_load('pubkey_mana'))
+    pubkey_mana_auth = pubkey_mana.derive_pda(bytearray([]))[0]
+    pubkey_mana_spla = spl_account(pubkey_mana_auth, pubkey_mint, pubkey_spl)
+    pubkey_config = pubkey_mana.derive_pda(bytearray(b'config') + pubkey_mint.p)[0]
+    rq = pxsol.core.Requisition(pubkey_mana, [], bytearray())
+    rq.account.append(pxsol.core.AccountMeta(user.pubkey, 3))  # [0] 管理员账户（签名者 + 可写）
+    rq.account.append(pxsol.core.AccountMeta(spl_account(user.pubkey, pubkey_mint, pubkey_spl), 1))  # [1] 接收代币的账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana_auth, 0))  # [2] 程序 PDA（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana_spla, 1))  # [3] PDA 的代币账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mint, 0))  # [4] 代币 Mint（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_config, 1))  # [5] 空投配置账户（可写，关闭国库时一并关闭）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_spl, 0))  # [6] Token 程序（SPL Token 或 Token-2022）
+    rq.data = data
+    tx = pxsol.core.Transaction.requisition_decode(user.pubkey, [rq])
+    tx.message.recent_blockhash = pxsol.base58.decode(pxsol.rpc.get_latest_blockhash({})['blockhash'])
+    tx.sign([user.prikey])
+    txid = pxsol.rpc.send_transaction(base64.b64encode(tx.serialize()).decode(), {})
+    pxsol.rpc.wait([txid])
+
+
+def withdraw():
+    # Withdraw from treasury, e.g. python make.py --prikey ADMIN withdraw [AMOUNT], omit AMOUNT to withdraw all
+    amount = int(args.args[1]) if len(args.args) > 1 else 0
+    pxsol.log.debugln(f'main: withdraw amount={amount}')
+    treasury_send(bytearray([0x04]) + bytearray(amount.to_bytes(8, 'big')))
+
+
+def close():
+    # Withdraw all and close treasury
+    pxsol.log.debugln(f'main: close treasury')
+    treasury_send(bytearray([0x05]))
+
+
+def refill():
+    # Refill treasury, e.g. python make.py --prikey FUNDER refill AMOUNT
+    user = pxsol.wallet.Wallet(pxsol.core.PriKey.base58_decode(args.prikey))
+    pubkey_mint = pxsol.core.PubKey.base58_decode(info_load('pubkey_mint'))
+    pubkey_spl = spl_program(pubkey_mint)  # 铸造账户所属的代币程序
+    pubkey_mana = pxsol.core.PubKey.base58_decode(info_load('pubkey_mana'))
+    pubkey_mana_auth = pubkey_mana.derive_pda(bytearray([]))[0]
+    pubkey_mana_spla = spl_account(pubkey_mana_auth, pubkey_mint, pubkey_spl)
+    amount = int(args.args[1])
+    rq = pxsol.core.Requisition(pubkey_mana, [], bytearray())
+    rq.account.append(pxsol.core.AccountMeta(user.pubkey, 3))  # [0] 出资账户（签名者 + 可写）
+    rq.account.append(pxsol.core.AccountMeta(spl_account(user.pubkey, pubkey_mint, pubkey_spl), 1))  # [1] 出资账户的代币账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana_auth, 0))  # [2] 程序 PDA（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana_spla, 1))  # [3] PDA 的代币账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mint, 0))  # [4] 代币 Mint（只读）
+    rq.account.append(pxsol.core.AccountMeta(pxsol.program.System.pubkey, 0))  # [5] 系统程序
+    rq.account.append(pxsol.core.AccountMeta(pubkey_spl, 0))  # [6] Token 程序（SPL Token 或 Token-2022）
+    rq.account.append(pxsol.core.AccountMeta(pxsol.program.AssociatedTokenAccount.pubkey, 0))  # [7] ATA 程序
+    rq.data = bytearray([0x06]) + bytearray(amount.to_bytes(8, 'big'))
+    tx = pxsol.core.Transaction.requisition_decode(user.pubkey, [rq])
+    tx.message.recent_blockhash = pxsol.base58.decode(pxsol.rpc.get_latest_blockhash({})['blockhash'])
+    tx.sign([user.prikey])
+    pxsol.log.debugln(f'main: refill amount={amount}')
+    txid = pxsol.rpc.send_transaction(base64.b64encode(tx.serialize()).decode(), {})
+    pxsol.rpc.wait([txid])
+
+
+def merkle_leaf(pubkey: pxsol.core.PubKey, amount: int) -> bytearray:
+    # 叶子 = sha256(user || amount)，amount 为 u64 大端序，与程序中的 merkle_leaf 一致
+    return bytearray(hashlib.sha256(pubkey.p + amount.to_bytes(8, 'big')).digest())
+
+
+def merkle_tree(leaves: list[bytearray]) -> list[list[bytearray]]:
+    # 自底向上构建 Merkle 树，某层节点数为奇数时最后一个节点与自身配对
+    tree = [leaves]
+    while len(tree[-1]) > 1:
+        layer = tree[-1]
+        tree.append([
+            bytearray(hashlib.sha256(layer[i] + layer[min(i + 1, len(layer) - 1)]).digest())
+            for i in range(0, len(layer), 2)
+        ])
+    return tree
+
+
+def merkle_proof(tree: list[list[bytearray]], index: int) -> list[bytearray]:
+    # 从叶子到根依次取出兄弟节点
+    proof = []
+    for layer in tree[:-1]:
+        proof.append(layer[min(index ^ 1, len(layer) - 1)])
+        index >>= 1
+    return proof
+
+
+def allowlist_load(path: str) -> list[tuple[pxsol.core.PubKey, int]]:
+    # 白名单文件格式：[["用户公钥", 数量], ...]，数量按最小单位
+    with open(path, 'r') as f:
+        return [(pxsol.core.PubKey.base58_decode(k), int(v)) for k, v in json.load(f)]
+
+
+def distributor():
+    # Create allowlist distributor, e.g. python make.py --prikey ADMIN distributor res/allowlist.json
+    user = pxsol.wallet.Wallet(pxsol.core.PriKey.base58_decode(args.prikey))
+    pubkey_mint = pxsol.core.PubKey.base58_decode(info_load('pubkey_mint'))
+    pubkey_mana = pxsol.core.PubKey.base58_decode(info_load('pubkey_mana'))
+    pubkey_config = pubkey_mana.derive_pda(bytearray(b'config') + pubkey_mint.p)[0]
+    pubkey_distributor = pubkey_mana.derive_pda(bytearray(b'distributor') + pubkey_mint.p)[0]
+    allowlist = allowlist_load(args.args[1])
+    tree = merkle_tree([merkle_leaf(k, v) for k, v in allowlist])
+    rq = pxsol.core.Requisition(pubkey_mana, [], bytearray())
+    rq.account.append(pxsol.core.AccountMeta(user.pubkey, 3))  # [0] 管理员账户（签名者 + 可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_distributor, 1))  # [1] 白名单分发账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mint, 0))  # [2] 代币 Mint（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_config, 0))  # [3] 空投配置账户（只读）
+    rq.account.append(pxsol.core.AccountMeta(pxsol.program.System.pubkey, 0))  # [4] 系统程序
+    rq.data = bytearray([0x02]) + tree[-1][0] + bytearray(len(allowlist).to_bytes(8, 'big'))  # 指令类型 + 根 + 叶子数量
+    tx = pxsol.core.Transaction.requisition_decode(user.pubkey, [rq])
+    tx.message.recent_blockhash = pxsol.base58.decode(pxsol.rpc.get_latest_blockhash({})['blockhash'])
+    tx.sign([user.prikey])
+    pxsol.log.debugln(f'main: create distributor root={tree[-1][0].hex()} count={len(allowlist)}')
+    txid = pxsol.rpc.send_transaction(base64.b64encode(tx.serialize()).decode(), {})
+    pxsol.rpc.wait([txid])
+
+
+def merkle():
+    # Claim from allowlist distributor, e.g. python make.py --prikey USER merkle res/allowlist.json
+    user = pxsol.wallet.Wallet(pxsol.core.PriKey.base58_decode(args.prikey))
+    pubkey_mint = pxsol.core.PubKey.base58_decode(info_load('pubkey_mint'))
+    pubkey_spl = spl_program(pubkey_mint)  # 铸造账户所属的代币程序
+    pubkey_mana = pxsol.core.PubKey.base58_decode(info_load('pubkey_mana'))
+    pubkey_mana_auth = pubkey_mana.derive_pda(bytearray([]))[0]
+    pubkey_mana_spla = spl_account(pubkey_mana_auth, pubkey_mint, pubkey_spl)
+    pubkey_config = pubkey_mana.derive_pda(bytearray(b'config') + pubkey_mint.p)[0]
+    pubkey_distributor = pubkey_mana.derive_pda(bytearray(b'distributor') + pubkey_mint.p)[0]
+    allowlist = allowlist_load(args.args[1])
+    index = [k.base58() for k, _ in allowlist].index(user.pubkey.base58())
+    amount = allowlist[index][1]
+    tree = merkle_tree([merkle_leaf(k, v) for k, v in allowlist])
+    rq = pxsol.core.Requisition(pubkey_mana, [], bytearray())
+    rq.account.append(pxsol.core.AccountMeta(user.pubkey, 3))  # [0] 用户账户（签名者 + 可写）
+    rq.account.append(pxsol.core.AccountMeta(spl_account(user.pubkey, pubkey_mint, pubkey_spl), 1))  # [1] 用户的代币账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana, 0))  # [2] 程序账户（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana_auth, 0))  # [3] 程序 PDA（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana_spla, 1))  # [4] PDA 的代币账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mint, 0))  # [5] 代币 Mint（只读）
+    rq.account.append(pxsol.core.AccountMeta(pxsol.program.System.pubkey, 0))  # [6] 系统程序
+    rq.account.append(pxsol.core.AccountMeta(pubkey_spl, 0))  # [7] Token 程序（SPL Token 或 Token-2022）
+    rq.account.append(pxsol.core.AccountMeta(pxsol.program.AssociatedTokenAccount.pubkey, 0))  # [8] ATA 程序
+    rq.account.append(pxsol.core.AccountMeta(pubkey_config, 0))  # [9] 空投配置账户（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_distributor, 1))  # [10] 白名单分发账户（可写）
+    rq.data = bytearray([0x03])  # 指令类型
+    rq.data.extend(index.to_bytes(8, 'big'))  # 白名单序号
+    rq.data.extend(amount.to_bytes(8, 'big'))  # 领取数量
+    for e in merkle_proof(tree, index):
+        rq.data.extend(e)  # Merkle 证明
+    tx = pxsol.core.Transaction.requisition_decode(user.pubkey, [rq])
+    tx.message.recent_blockhash = pxsol.base58.decode(pxsol.rpc.get_latest_blockhash({})['blockhash'])
+    tx.sign([user.prikey])
+    pxsol.log.debugln(f'main: request merkle airdrop index={index} amount={amount}')
+    txid = pxsol.rpc.send_transaction(base64.b64encode(tx.serialize()).decode(), {})
+    pxsol.rpc.wait([txid])
+    splcnt = user.spl_balance(pubkey_mint)
+    pxsol.log.debugln(f'main: request merkle airdrop done recv={splcnt[0] / 10**splcnt[1]}')
+
+
 def airdrop():
     # 1. 准备账户地址
     user = pxsol.wallet.Wallet(pxsol.core.PriKey.base58_decode(args.prikey))  # 用户钱包（领取空投的人）
     pubkey_mint = pxsol.core.PubKey.base58_decode(info_load('pubkey_mint'))  # 代币 Mint 地址
+    pubkey_spl = spl_program(pubkey_mint)  # 铸造账户所属的代币程序
     pubkey_mana = pxsol.core.PubKey.base58_decode(info_load('pubkey_mana'))  # 空投程序地址
     
     # 2. 派生程序的 PDA 和其代币账户
     pubkey_mana_seed = bytearray([])  # 空种子
     pubkey_mana_auth = pubkey_mana.derive_pda(pubkey_mana_seed)[0]  # 程序的 PDA（持有代币）
-    pubkey_mana_spla = pxsol.wallet.Wallet.view_only(pubkey_mana_auth).spl_account(pubkey_mint)  # PDA 的代币账户（ATA）
+    pubkey_mana_spla = spl_account(pubkey_mana_auth, pubkey_mint, pubkey_spl)  # PDA 的代币账户（ATA）
+    pubkey_config = pubkey_mana.derive_pda(bytearray(b'config') + pubkey_mint.p)[0]  # 空投配置账户
+    pubkey_claim = pubkey_mana.derive_pda(bytearray(b'claim') + user.pubkey.p + pubkey_mint.p)[0]  # 用户的领取记录
     
     # 3. 构建交易指令（Instruction）
     rq = pxsol.core.Requisition(pubkey_mana, [], bytearray())  # 创建指令，调用空投程序
     # 添加指令需要的账户（按顺序传递给程序）
     rq.account.append(pxsol.core.AccountMeta(user.pubkey, 3))  # [0] 用户账户（签名者 + 可写）
-    rq.account.append(pxsol.core.AccountMeta(user.spl_account(pubkey_mint), 1))  # [1] 用户的代币账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(spl_account(user.pubkey, pubkey_mint, pubkey_spl), 1))  # [1] 用户的代币账户（可写）
     rq.account.append(pxsol.core.AccountMeta(pubkey_mana, 0))  # [2] 程序账户（只读）
     rq.account.append(pxsol.core.AccountMeta(pubkey_mana_auth, 0))  # [3] 程序 PDA（只读）
     rq.account.append(pxsol.core.AccountMeta(pubkey_mana_spla, 1))  # [4] PDA 的代币账户（可写）
     rq.account.append(pxsol.core.AccountMeta(pubkey_mint, 0))  # [5] 代币 Mint（只读）
     rq.account.append(pxsol.core.AccountMeta(pxsol.program.System.pubkey, 0))  # [6] 系统程序
-    rq.account.append(pxsol.core.AccountMeta(pxsol.program.Token.pubkey, 0))  # [7] Token 程序
+    rq.account.append(pxsol.core.AccountMeta(pubkey_spl, 0))  # [7] Token 程序（SPL Token 或 Token-2022）
     rq.account.append(pxsol.core.AccountMeta(pxsol.program.AssociatedTokenAccount.pubkey, 0))  # [8] ATA 程序
+    rq.account.append(pxsol.core.AccountMeta(pubkey_config, 0))  # [9] 空投配置账户（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_claim, 1))  # [10] 领取记录（可写）
     rq.data = bytearray()  # 指令数据（空，程序不需要额外参数）
     
     # 4. 构建交易
@@ -136,5 +365,51 @@ def airdrop():
     pxsol.log.debugln(f'main: request spl airdrop done recv={splcnt[0] / 10**splcnt[1]}')
 
 
+
+def vesting_send(tag: int):
+    # 创建归属计划（0x07）或释放已解锁的代币（0x08），前 9 个账户与 airdrop 相同
+    user = pxsol.wallet.Wallet(pxsol.core.PriKey.base58_decode(args.prikey))
+    pubkey_mint = pxsol.core.PubKey.base58_decode(info_load('pubkey_mint'))
+    pubkey_spl = spl_program(pubkey_mint)  # 铸造账户所属的代币程序
+    pubkey_mana = pxsol.core.PubKey.base58_decode(info_load('pubkey_mana'))
+    pubkey_mana_auth = pubkey_mana.derive_pda(bytearray([]))[0]
+    pubkey_mana_spla = spl_account(pubkey_mana_auth, pubkey_mint, pubkey_spl)
+    pubkey_vesting = pubkey_mana.derive_pda(bytearray(b'vesting') + user.pubkey.p + pubkey_mint.p)[0]  # 归属计划
+    rq = pxsol.core.Requisition(pubkey_mana, [], bytearray())
+    rq.account.append(pxsol.core.AccountMeta(user.pubkey, 3))  # [0] 用户账户（签名者 + 可写）
+    rq.account.append(pxsol.core.AccountMeta(spl_account(user.pubkey, pubkey_mint, pubkey_spl), 1))  # [1] 用户的代币账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana, 0))  # [2] 程序账户（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana_auth, 0))  # [3] 程序 PDA（只读）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mana_spla, 1))  # [4] PDA 的代币账户（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_mint, 0))  # [5] 代币 Mint（只读）
+    rq.account.append(pxsol.core.AccountMeta(pxsol.program.System.pubkey, 0))  # [6] 系统程序
+    rq.account.append(pxsol.core.AccountMeta(pubkey_spl, 0))  # [7] Token 程序（SPL Token 或 Token-2022）
+    rq.account.append(pxsol.core.AccountMeta(pxsol.program.AssociatedTokenAccount.pubkey, 0))  # [8] ATA 程序
+    pubkey_config = pubkey_mana.derive_pda(bytearray(b'config') + pubkey_mint.p)[0]
+    rq.account.append(pxsol.core.AccountMeta(pubkey_config, 1))  # [9] 空投配置账户（可写，记录保留总量）
+    if tag == 0x07:
+        pubkey_claim = pubkey_mana.derive_pda(bytearray(b'claim') + user.pubkey.p + pubkey_mint.p)[0]
+        rq.account.append(pxsol.core.AccountMeta(pubkey_claim, 1))  # [10] 领取记录（可写）
+    rq.account.append(pxsol.core.AccountMeta(pubkey_vesting, 1))  # 归属计划（可写）
+    rq.data = bytearray([tag])
+    tx = pxsol.core.Transaction.requisition_decode(user.pubkey, [rq])
+    tx.message.recent_blockhash = pxsol.base58.decode(pxsol.rpc.get_latest_blockhash({})['blockhash'])
+    tx.sign([user.prikey])
+    txid = pxsol.rpc.send_transaction(base64.b64encode(tx.serialize()).decode(), {})
+    pxsol.rpc.wait([txid])
+
+
+def vest():
+    # Open a vesting schedule instead of receiving tokens immediately
+    pxsol.log.debugln(f'main: request vesting')
+    vesting_send(0x07)
+
+
+def release():
+    # Release unlocked tokens from the vesting schedule
+    pxsol.log.debugln(f'main: request release')
+    vesting_send(0x08)
+
+
 if __name__ == '__main__':
     eval(f'{args.args[0]}()')
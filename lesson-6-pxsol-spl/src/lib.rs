@@ -1,78 +1,1172 @@
 #![allow(unexpected_cfgs)]
 
+use solana_program::sysvar::Sysvar;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+
 // 声明程序入口点
 solana_program::entrypoint!(process_instruction);
 
-pub fn process_instruction(
-    _: &solana_program::pubkey::Pubkey,
+/// 配置账户的 PDA 种子前缀，完整种子为 [b"config", mint]
+pub const CONFIG_SEED: &[u8] = b"config";
+/// 配置账户数据长度：管理员公钥（32 字节）+ 每次空投数量 + 冷却时间 + 累计上限 + 锁仓期 + 线性释放时长（各 8 字节，大端序）
+/// + 是否为归属模式（1 字节）+ 尚未释放的归属总量（8 字节，大端序）
+pub const CONFIG_SIZE: usize = 32 + 8 + 8 + 8 + 8 + 8 + 1 + 8;
+/// 领取记录的 PDA 种子前缀，完整种子为 [b"claim", user, mint]
+pub const CLAIM_SEED: &[u8] = b"claim";
+/// 领取记录数据长度：上次领取的 slot + 累计领取数量（各为 u64，大端序）
+pub const CLAIM_SIZE: usize = 8 + 8;
+/// 白名单分发账户的 PDA 种子前缀，完整种子为 [b"distributor", mint]
+pub const DISTRIBUTOR_SEED: &[u8] = b"distributor";
+/// 白名单分发账户头部长度：Merkle 根（32 字节）+ 叶子数量（u64，大端序），其后为已领取位图
+pub const DISTRIBUTOR_HEAD: usize = 32 + 8;
+/// 归属计划账户的 PDA 种子前缀，完整种子为 [b"vesting", user, mint]
+pub const VESTING_SEED: &[u8] = b"vesting";
+/// 归属计划账户数据长度：总量 + 已释放数量（u64）+ 开始时间 + 锁仓期 + 线性释放时长（i64，秒），均为大端序
+pub const VESTING_SIZE: usize = 8 + 8 + 8 + 8 + 8;
+
+/// 空投程序的自定义错误，以 ProgramError::Custom(错误码) 的形式返回
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AirdropError {
+    /// 距离上次领取还未超过冷却时间
+    ClaimCooldown = 0,
+    /// 本次领取后将超过该钱包的累计领取上限
+    ClaimCapExceeded = 1,
+    /// Merkle 证明无效（叶子、序号或证明路径与根不匹配）
+    InvalidProof = 2,
+    /// 该白名单序号已经领取过
+    AlreadyClaimed = 3,
+    /// 用户的关联代币账户地址错误
+    WrongUserAta = 4,
+    /// Mana 的 PDA 权限账户地址错误
+    WrongManaPda = 5,
+    /// Mana 的关联代币账户地址错误
+    WrongManaAta = 6,
+    /// 代币程序不是 SPL Token 或 SPL Token 2022，或与铸造账户的所有者不一致
+    WrongTokenProgram = 7,
+    /// 关联代币账户程序地址错误
+    WrongAtaProgram = 8,
+    /// System 程序地址错误
+    WrongSystemProgram = 9,
+    /// Mana 程序账户不是当前程序
+    WrongManaProgram = 10,
+    /// 配置账户地址错误或未初始化
+    WrongConfig = 11,
+    /// 领取记录账户地址错误
+    WrongClaimRecord = 12,
+    /// 白名单分发账户地址错误或未初始化
+    WrongDistributor = 13,
+    /// 签名者不是管理员（或首次配置时不是代币的铸造权限）
+    Unauthorized = 14,
+    /// 归属计划账户地址错误
+    WrongVesting = 15,
+    /// 该钱包在该代币下已有归属计划
+    VestingExists = 16,
+    /// 当前没有可释放的代币
+    NothingToRelease = 17,
+    /// 指令与配置的领取模式不符（归属模式下不能直接空投，非归属模式下不能创建归属计划）
+    WrongMode = 18,
+    /// 国库余额将低于尚未释放的归属总量
+    ReservedForVesting = 19,
+}
+
+impl From<AirdropError> for solana_program::program_error::ProgramError {
+    fn from(e: AirdropError) -> Self {
+        solana_program::program_error::ProgramError::Custom(e as u32)
+    }
+}
+
+/// 条件不满足时返回指定的错误
+fn require(cond: bool, err: impl Into<solana_program::program_error::ProgramError>) -> solana_program::entrypoint::ProgramResult {
+    if cond {
+        Ok(())
+    } else {
+        Err(err.into())
+    }
+}
+
+/// 验证账户已签名
+fn require_signer(account: &solana_program::account_info::AccountInfo) -> solana_program::entrypoint::ProgramResult {
+    require(account.is_signer, solana_program::program_error::ProgramError::MissingRequiredSignature)
+}
+
+/// 验证 System 程序地址
+fn require_system_program(account: &solana_program::account_info::AccountInfo) -> solana_program::entrypoint::ProgramResult {
+    require(account.key == &solana_program::system_program::id(), AirdropError::WrongSystemProgram)
+}
+
+/// 验证关联代币账户程序地址
+fn require_ata_program(account: &solana_program::account_info::AccountInfo) -> solana_program::entrypoint::ProgramResult {
+    require(account.key == &spl_associated_token_account::id(), AirdropError::WrongAtaProgram)
+}
+
+/// 创建由当前程序所有的 PDA 账户（由 payer 支付租金）
+/// - 账户还没有 lamports 时直接 create_account
+/// - 任何人都可以预先向 PDA 地址转入 lamports，此时 create_account 会失败，
+///   改为补足免租金额 + allocate + assign（后两步需要 PDA 签名，别人无法抢先完成）
+fn create_pda<'a>(
+    payer: &solana_program::account_info::AccountInfo<'a>,
+    account: &solana_program::account_info::AccountInfo<'a>,
+    size: usize,
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo<'a>],
+    seeds: &[&[u8]],
+) -> solana_program::entrypoint::ProgramResult {
+    let rent_exemption = solana_program::rent::Rent::get()?.minimum_balance(size);
+    let lamports = account.lamports();
+    if lamports == 0 {
+        return solana_program::program::invoke_signed(
+            &solana_program::system_instruction::create_account(
+                payer.key,               // 付款账户
+                account.key,             // 新账户地址（PDA）
+                rent_exemption,          // 转账金额（租金豁免）
+                size as u64,             // 数据大小
+                program_id,              // 账户所有者（当前程序）
+            ),
+            accounts,
+            &[seeds],
+        );
+    }
+    if rent_exemption > lamports {
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(payer.key, account.key, rent_exemption - lamports),
+            accounts,
+        )?;
+    }
+    solana_program::program::invoke_signed(
+        &solana_program::system_instruction::allocate(account.key, size as u64),
+        accounts,
+        &[seeds],
+    )?;
+    solana_program::program::invoke_signed(
+        &solana_program::system_instruction::assign(account.key, program_id),
+        accounts,
+        &[seeds],
+    )
+}
+
+/// 空投配置
+pub struct Config {
+    /// 管理员
+    pub admin: solana_program::pubkey::Pubkey,
+    /// 每次空投的数量（按最小单位）
+    pub amount: u64,
+    /// 两次领取之间至少间隔的 slot 数，0 表示不限制
+    pub cooldown: u64,
+    /// 每个钱包累计可领取的上限（按最小单位），0 表示不限制
+    pub cap: u64,
+    /// 归属模式下的锁仓期（秒），锁仓期内不释放
+    pub cliff: i64,
+    /// 归属模式下从开始到全部释放的时长（秒），0 表示锁仓期结束后一次性释放
+    pub duration: i64,
+    /// 是否为归属模式：是则只能创建归属计划（0x07），否则只能直接空投（0x00）
+    pub vesting: bool,
+    /// 所有归属计划中尚未释放的总量，国库余额不能低于它
+    pub reserved: u64,
+}
+
+impl Config {
+    /// 从配置账户中读取
+    pub fn load(account_config: &solana_program::account_info::AccountInfo) -> Self {
+        let data = account_config.data.borrow();
+        Self {
+            admin: solana_program::pubkey::Pubkey::new_from_array(data[0..32].try_into().unwrap()),
+            amount: u64::from_be_bytes(data[32..40].try_into().unwrap()),
+            cooldown: u64::from_be_bytes(data[40..48].try_into().unwrap()),
+            cap: u64::from_be_bytes(data[48..56].try_into().unwrap()),
+            cliff: i64::from_be_bytes(data[56..64].try_into().unwrap()),
+            duration: i64::from_be_bytes(data[64..72].try_into().unwrap()),
+            vesting: data[72] != 0,
+            reserved: u64::from_be_bytes(data[73..81].try_into().unwrap()),
+        }
+    }
+
+    /// 写回配置账户
+    pub fn save(&self, account_config: &solana_program::account_info::AccountInfo) {
+        let mut data = account_config.data.borrow_mut();
+        data[0..32].copy_from_slice(self.admin.as_ref());
+        data[32..40].copy_from_slice(&self.amount.to_be_bytes());
+        data[40..48].copy_from_slice(&self.cooldown.to_be_bytes());
+        data[48..56].copy_from_slice(&self.cap.to_be_bytes());
+        data[56..64].copy_from_slice(&self.cliff.to_be_bytes());
+        data[64..72].copy_from_slice(&self.duration.to_be_bytes());
+        data[72] = self.vesting as u8;
+        data[73..81].copy_from_slice(&self.reserved.to_be_bytes());
+    }
+}
+
+/// 归属计划：总量在 start + cliff 之前全部锁定，之后按 start 起算的经过时间在 duration 内线性释放
+pub struct Vesting {
+    /// 归属总量（按最小单位）
+    pub total: u64,
+    /// 已释放的数量
+    pub released: u64,
+    /// 开始时间（Unix 时间戳，秒）
+    pub start: i64,
+    /// 锁仓期（秒）
+    pub cliff: i64,
+    /// 线性释放时长（秒）
+    pub duration: i64,
+}
+
+impl Vesting {
+    /// 从归属计划账户中读取
+    pub fn load(account_vesting: &solana_program::account_info::AccountInfo) -> Self {
+        let data = account_vesting.data.borrow();
+        Self {
+            total: u64::from_be_bytes(data[0..8].try_into().unwrap()),
+            released: u64::from_be_bytes(data[8..16].try_into().unwrap()),
+            start: i64::from_be_bytes(data[16..24].try_into().unwrap()),
+            cliff: i64::from_be_bytes(data[24..32].try_into().unwrap()),
+            duration: i64::from_be_bytes(data[32..40].try_into().unwrap()),
+        }
+    }
+
+    /// 写回归属计划账户
+    pub fn save(&self, account_vesting: &solana_program::account_info::AccountInfo) {
+        let mut data = account_vesting.data.borrow_mut();
+        data[0..8].copy_from_slice(&self.total.to_be_bytes());
+        data[8..16].copy_from_slice(&self.released.to_be_bytes());
+        data[16..24].copy_from_slice(&self.start.to_be_bytes());
+        data[24..32].copy_from_slice(&self.cliff.to_be_bytes());
+        data[32..40].copy_from_slice(&self.duration.to_be_bytes());
+    }
+
+    /// 截至 now 已解锁的总量（包含已释放的部分）
+    pub fn unlocked(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            return 0;
+        }
+        if elapsed >= self.duration {
+            return self.total;
+        }
+        (self.total as u128 * elapsed as u128 / self.duration as u128) as u64
+    }
+}
+
+/// 验证代币程序为 SPL Token 或 SPL Token 2022，且铸造账户归该程序所有
+fn token_program_check(
+    account_spl: &solana_program::account_info::AccountInfo,
+    account_mint: &solana_program::account_info::AccountInfo,
+) -> solana_program::entrypoint::ProgramResult {
+    require(account_spl.key == &spl_token::id() || account_spl.key == &spl_token_2022::id(), AirdropError::WrongTokenProgram)?;
+    require(account_mint.owner == account_spl.key, AirdropError::WrongTokenProgram)
+}
+
+/// 计算 Token-2022 铸造账户在当前 epoch 对 amount 收取的转账手续费，没有 TransferFee 扩展时为 0
+fn mint_transfer_fee(
+    account_mint: &solana_program::account_info::AccountInfo,
+    amount: u64,
+) -> Result<u64, solana_program::program_error::ProgramError> {
+    let data = account_mint.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    match mint.get_extension::<spl_token_2022::extension::transfer_fee::TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(solana_program::clock::Clock::get()?.epoch, amount)
+            .ok_or(solana_program::program_error::ProgramError::ArithmeticOverflow),
+        Err(_) => Ok(0),
+    }
+}
+
+/// 根据代币程序调用 transfer_checked，返回接收方实际到账的数量
+/// - SPL Token：直接构造 spl_token 的指令
+/// - Token-2022：通过 spl_token_2022::onchain::invoke_transfer_checked 调用。铸造账户带 TransferHook 扩展时，
+///   会从 extra 中找出钩子程序、额外账户元数据（extra account metas）账户及其列出的账户一并传入；
+///   带 TransferFee 扩展时，手续费由接收方承担，到账数量为扣除手续费后的数量
+#[allow(clippy::too_many_arguments)]
+fn token_transfer<'a>(
+    spl: &solana_program::account_info::AccountInfo<'a>,
+    from: &solana_program::account_info::AccountInfo<'a>,
+    mint: &solana_program::account_info::AccountInfo<'a>,
+    into: &solana_program::account_info::AccountInfo<'a>,
+    auth: &solana_program::account_info::AccountInfo<'a>,
+    extra: &[solana_program::account_info::AccountInfo<'a>],
+    amount: u64,
+    seeds: &[&[&[u8]]],
+) -> Result<u64, solana_program::program_error::ProgramError> {
+    let decimals = mint_decimals(mint)?;
+    if spl.key == &spl_token::id() {
+        solana_program::program::invoke_signed(
+            &spl_token::instruction::transfer_checked(
+                spl.key, from.key, mint.key, into.key, auth.key, &[], amount, decimals,
+            )?,
+            &[from.clone(), mint.clone(), into.clone(), auth.clone()],
+            seeds,
+        )?;
+        return Ok(amount);
+    }
+    let fee = mint_transfer_fee(mint, amount)?;
+    spl_token_2022::onchain::invoke_transfer_checked(
+        spl.key,
+        from.clone(),
+        mint.clone(),
+        into.clone(),
+        auth.clone(),
+        extra,
+        amount,
+        decimals,
+        seeds,
+    )?;
+    Ok(amount - fee)
+}
+
+/// 根据代币程序构造 close_account 指令
+fn token_close_account(
+    spl: &solana_program::pubkey::Pubkey,
+    account: &solana_program::pubkey::Pubkey,
+    into: &solana_program::pubkey::Pubkey,
+    auth: &solana_program::pubkey::Pubkey,
+) -> Result<solana_program::instruction::Instruction, solana_program::program_error::ProgramError> {
+    if spl == &spl_token::id() {
+        spl_token::instruction::close_account(spl, account, into, auth, &[])
+    } else {
+        spl_token_2022::instruction::close_account(spl, account, into, auth, &[])
+    }
+}
+
+/// 读取代币账户的余额（兼容 SPL Token 与带扩展的 Token-2022 代币账户）
+fn token_balance(account: &solana_program::account_info::AccountInfo) -> Result<u64, solana_program::program_error::ProgramError> {
+    let data = account.data.borrow();
+    Ok(StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?.base.amount)
+}
+
+/// 从代币铸造账户中读取小数位数（兼容 SPL Token 铸造账户以及带扩展的 Token-2022 铸造账户）
+fn mint_decimals(account_mint: &solana_program::account_info::AccountInfo) -> Result<u8, solana_program::program_error::ProgramError> {
+    let data = account_mint.data.borrow();
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    Ok(mint.base.decimals)
+}
+
+/// Merkle 树叶子：hash(user, amount)，amount 为 u64 大端序
+pub fn merkle_leaf(user: &solana_program::pubkey::Pubkey, amount: u64) -> [u8; 32] {
+    solana_program::hash::hashv(&[user.as_ref(), &amount.to_be_bytes()]).to_bytes()
+}
+
+/// 验证 Merkle 证明
+/// - index 的第 i 位表示第 i 层当前节点在右侧（1）还是左侧（0），因此序号与叶子位置绑定，不能伪造
+pub fn merkle_verify(root: &[u8; 32], leaf: [u8; 32], index: u64, proof: &[[u8; 32]]) -> bool {
+    let mut node = leaf;
+    let mut index = index;
+    for sibling in proof {
+        node = if index & 1 == 0 {
+            solana_program::hash::hashv(&[&node, sibling]).to_bytes()
+        } else {
+            solana_program::hash::hashv(&[sibling, &node]).to_bytes()
+        };
+        index >>= 1;
+    }
+    index == 0 && &node == root
+}
+
+/// 空投类指令共用的前 9 个账户
+///   [0] 用户账户（需要签名，可写）
+///   [1] 用户的关联代币账户（可写）
+///   [2] Mana 程序账户（即当前程序）
+///   [3] Mana 的 PDA 权限账户
+///   [4] Mana 的关联代币账户（可写）
+///   [5] 代币铸造账户
+///   [6] System 程序
+///   [7] 代币程序（SPL Token 或 SPL Token 2022，须与铸造账户所属程序一致）
+///   [8] 关联代币账户程序
+pub struct Mana<'a, 'b> {
+    pub user: &'a solana_program::account_info::AccountInfo<'b>,
+    pub user_spla: &'a solana_program::account_info::AccountInfo<'b>,
+    pub mana_auth: &'a solana_program::account_info::AccountInfo<'b>,
+    pub mana_auth_bump: u8,
+    pub mana_spla: &'a solana_program::account_info::AccountInfo<'b>,
+    pub mint: &'a solana_program::account_info::AccountInfo<'b>,
+    pub spl: &'a solana_program::account_info::AccountInfo<'b>,
+}
+
+impl<'a, 'b> Mana<'a, 'b> {
+    /// 从账户列表中依次取出前 9 个账户并验证
+    pub fn load(
+        program_id: &solana_program::pubkey::Pubkey,
+        accounts_iter: &mut std::slice::Iter<'a, solana_program::account_info::AccountInfo<'b>>,
+    ) -> Result<Self, solana_program::program_error::ProgramError> {
+        // ========== 1. 提取账户信息 ==========
+        let account_user = solana_program::account_info::next_account_info(accounts_iter)?;          // 用户账户
+        let account_user_spla = solana_program::account_info::next_account_info(accounts_iter)?;     // 用户的关联代币账户
+        let account_mana = solana_program::account_info::next_account_info(accounts_iter)?;          // Mana 程序账户
+        let account_mana_auth = solana_program::account_info::next_account_info(accounts_iter)?;     // Mana 的 PDA 权限账户
+        let account_mana_spla = solana_program::account_info::next_account_info(accounts_iter)?;     // Mana 的关联代币账户
+        let account_mint = solana_program::account_info::next_account_info(accounts_iter)?;          // 代币铸造账户
+        let account_system = solana_program::account_info::next_account_info(accounts_iter)?;        // System 程序
+        let account_spl = solana_program::account_info::next_account_info(accounts_iter)?;           // 代币程序
+        let account_ata = solana_program::account_info::next_account_info(accounts_iter)?;           // 关联代币账户程序
+
+        // ========== 2. 账户验证 ==========
+        // 验证用户签名
+        require_signer(account_user)?;
+
+        // 验证 Mana 程序账户就是当前程序，以及 System 程序与关联代币账户程序地址正确
+        require(account_mana.key == program_id, AirdropError::WrongManaProgram)?;
+        require_system_program(account_system)?;
+        require_ata_program(account_ata)?;
+
+        // 验证代币程序为 SPL Token 或 SPL Token 2022，且与铸造账户的所有者一致
+        token_program_check(account_spl, account_mint)?;
+
+        // 验证用户的关联代币账户地址正确（按铸造账户所属的代币程序派生）
+        let account_user_spla_calc = spl_associated_token_account::get_associated_token_address_with_program_id(
+            account_user.key,
+            account_mint.key,
+            account_spl.key,
+        );
+        require(account_user_spla.key == &account_user_spla_calc, AirdropError::WrongUserAta)?;
+
+        // 验证 Mana 的 PDA 权限账户地址正确
+        let account_mana_auth_calc = solana_program::pubkey::Pubkey::find_program_address(&[&[]], account_mana.key);
+        require(account_mana_auth.key == &account_mana_auth_calc.0, AirdropError::WrongManaPda)?;
+
+        // 验证 Mana 的关联代币账户地址正确
+        let account_mana_spla_calc = spl_associated_token_account::get_associated_token_address_with_program_id(
+            account_mana_auth.key,
+            account_mint.key,
+            account_spl.key,
+        );
+        require(account_mana_spla.key == &account_mana_spla_calc, AirdropError::WrongManaAta)?;
+
+        Ok(Self {
+            user: account_user,
+            user_spla: account_user_spla,
+            mana_auth: account_mana_auth,
+            mana_auth_bump: account_mana_auth_calc.1,
+            mana_spla: account_mana_spla,
+            mint: account_mint,
+            spl: account_spl,
+        })
+    }
+
+    /// 为用户创建关联代币账户（如果不存在），然后从 Mana 向用户转账 amount 个代币（按最小单位）
+    /// - extra 为指令账户列表末尾的附加账户，转账钩子需要的账户放在这里
+    /// - 扣除转账手续费后的到账数量会写入日志，并作为返回数据（u64，大端序）
+    pub fn transfer(
+        &self,
+        accounts: &[solana_program::account_info::AccountInfo<'b>],
+        extra: &[solana_program::account_info::AccountInfo<'b>],
+        amount: u64,
+    ) -> solana_program::entrypoint::ProgramResult {
+        // 创建用户的关联代币账户（如果不存在）
+        solana_program::program::invoke(
+            &spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                self.user.key,
+                self.user.key,
+                self.mint.key,
+                self.spl.key,
+            ),
+            accounts,
+        )?;
+
+        // 从 Mana 向用户转账代币（使用 PDA 签名）
+        let net = token_transfer(
+            self.spl,                           // SPL 程序
+            self.mana_spla,                     // 源账户（Mana 的代币账户）
+            self.mint,                          // 代币铸造账户
+            self.user_spla,                     // 目标账户（用户的代币账户）
+            self.mana_auth,                     // 转账权限（Mana 的 PDA）
+            extra,                              // 附加账户（转账钩子）
+            amount,                             // 转账数量
+            &[&[&[], &[self.mana_auth_bump]]],  // PDA 签名种子
+        )?;
+        solana_program::msg!("airdrop {} to {}, received {} after transfer fee", amount, self.user.key, net);
+        solana_program::program::set_return_data(&net.to_be_bytes());
+        Ok(())
+    }
+}
+
+/// 读取空投配置，检查冷却时间与累计上限并更新领取记录
+/// - 从 accounts_iter 中依次取出配置账户和领取记录账户
+/// - 首次领取时创建领取记录账户（由用户支付租金）
+fn claim<'a, 'b>(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo<'b>],
+    accounts_iter: &mut std::slice::Iter<'a, solana_program::account_info::AccountInfo<'b>>,
+    mana: &Mana<'a, 'b>,
+) -> Result<Config, solana_program::program_error::ProgramError> {
+    let account_config = solana_program::account_info::next_account_info(accounts_iter)?;        // 配置账户
+    let account_claim = solana_program::account_info::next_account_info(accounts_iter)?;         // 领取记录账户
+
+    // 验证配置账户地址正确，且已由管理员初始化
+    let account_config_calc =
+        solana_program::pubkey::Pubkey::find_program_address(&[CONFIG_SEED, mana.mint.key.as_ref()], program_id);
+    require(account_config.key == &account_config_calc.0, AirdropError::WrongConfig)?;
+    require(account_config.owner == program_id, AirdropError::WrongConfig)?;
+
+    // 验证领取记录账户地址正确
+    let account_claim_calc = solana_program::pubkey::Pubkey::find_program_address(
+        &[CLAIM_SEED, mana.user.key.as_ref(), mana.mint.key.as_ref()],
+        program_id,
+    );
+    require(account_claim.key == &account_claim_calc.0, AirdropError::WrongClaimRecord)?;
+
+    let config = Config::load(account_config);
+    let slot = solana_program::clock::Clock::get()?.slot;
+    // 以所有者判断领取记录是否存在，预先转入 lamports 不能阻止用户领取
+    if account_claim.owner != program_id {
+        // 首次领取：创建领取记录账户（由用户支付租金）
+        create_pda(
+            mana.user,
+            account_claim,
+            CLAIM_SIZE,
+            program_id,
+            accounts,
+            &[CLAIM_SEED, mana.user.key.as_ref(), mana.mint.key.as_ref(), &[account_claim_calc.1]],
+        )?;
+    } else {
+        let data = account_claim.data.borrow();
+        let last_slot = u64::from_be_bytes(data[0..8].try_into().unwrap());
+        if config.cooldown != 0 && slot < last_slot.saturating_add(config.cooldown) {
+            return Err(AirdropError::ClaimCooldown.into());
+        }
+    }
+    let total = u64::from_be_bytes(account_claim.data.borrow()[8..16].try_into().unwrap());
+    let total = total.checked_add(config.amount).ok_or(AirdropError::ClaimCapExceeded)?;
+    if config.cap != 0 && total > config.cap {
+        return Err(AirdropError::ClaimCapExceeded.into());
+    }
+    account_claim.data.borrow_mut()[0..8].copy_from_slice(&slot.to_be_bytes());
+    account_claim.data.borrow_mut()[8..16].copy_from_slice(&total.to_be_bytes());
+    Ok(config)
+}
+
+/// 空投指令处理函数
+///
+/// 功能：从 Mana 的代币账户向用户转账配置中设定数量的代币
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表
+///   [0..9] 见 Mana
+///   [9] 配置账户（PDA，种子为 [b"config", mint]）
+///   [10] 领取记录账户（PDA，种子为 [b"claim", user, mint]，可写，首次领取时创建）
+///   [11..] 转账钩子需要的附加账户（可选）
+pub fn process_instruction_airdrop(
+    program_id: &solana_program::pubkey::Pubkey,
     accounts: &[solana_program::account_info::AccountInfo],
-    _: &[u8],
 ) -> solana_program::entrypoint::ProgramResult {
-    // ========== 1. 提取账户信息 ==========
+    // ========== 1. 提取并验证账户 ==========
     let accounts_iter = &mut accounts.iter();
-    let account_user = solana_program::account_info::next_account_info(accounts_iter)?;          // 用户账户
-    let account_user_spla = solana_program::account_info::next_account_info(accounts_iter)?;     // 用户的关联代币账户
-    let account_mana = solana_program::account_info::next_account_info(accounts_iter)?;          // Mana 程序账户
+    let mana = Mana::load(program_id, accounts_iter)?;
+
+    // ========== 2. 检查冷却时间与累计上限，并更新领取记录 ==========
+    let config = claim(program_id, accounts, accounts_iter, &mana)?;
+    // 归属模式下只能创建归属计划，不能绕过锁仓直接领取
+    require(!config.vesting, AirdropError::WrongMode)?;
+
+    // ========== 3. 从 Mana 向用户转账代币 ==========
+    mana.transfer(accounts, accounts_iter.as_slice(), config.amount)
+}
+
+/// 白名单领取指令处理函数
+///
+/// 功能：用户提供白名单中的序号、数量和 Merkle 证明，验证通过后从 Mana 转账对应数量的代币
+/// - 为归属计划保留的代币不能领取
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表
+///   [0..9] 见 Mana
+///   [9] 配置账户（PDA，种子为 [b"config", mint]）
+///   [10] 白名单分发账户（PDA，种子为 [b"distributor", mint]，可写）
+///   [11..] 转账钩子需要的附加账户（可选）
+/// - data: 序号（u64，大端序）+ 数量（u64，大端序）+ 若干个 32 字节的证明节点（从叶子到根）
+pub fn process_instruction_merkle_claim(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+    data: &[u8],
+) -> solana_program::entrypoint::ProgramResult {
+    // 解析账户列表
+    let accounts_iter = &mut accounts.iter();
+    let mana = Mana::load(program_id, accounts_iter)?;
+    let account_config = solana_program::account_info::next_account_info(accounts_iter)?;        // 配置账户
+    let account_distributor = solana_program::account_info::next_account_info(accounts_iter)?;   // 白名单分发账户
+
+    // 验证配置账户
+    let account_config_calc =
+        solana_program::pubkey::Pubkey::find_program_address(&[CONFIG_SEED, mana.mint.key.as_ref()], program_id);
+    require(account_config.key == &account_config_calc.0, AirdropError::WrongConfig)?;
+    require(account_config.owner == program_id, AirdropError::WrongConfig)?;
+
+    // 验证白名单分发账户
+    let account_distributor_calc =
+        solana_program::pubkey::Pubkey::find_program_address(&[DISTRIBUTOR_SEED, mana.mint.key.as_ref()], program_id);
+    require(account_distributor.key == &account_distributor_calc.0, AirdropError::WrongDistributor)?;
+    require(account_distributor.owner == program_id, AirdropError::WrongDistributor)?;
+
+    // 解析指令数据
+    if data.len() < 16 || !(data.len() - 16).is_multiple_of(32) {
+        return Err(solana_program::program_error::ProgramError::InvalidInstructionData);
+    }
+    let index = u64::from_be_bytes(data[0..8].try_into().unwrap());
+    let amount = u64::from_be_bytes(data[8..16].try_into().unwrap());
+    let proof: Vec<[u8; 32]> = data[16..].chunks_exact(32).map(|e| e.try_into().unwrap()).collect();
+
+    // 验证 Merkle 证明，并在位图中标记已领取
+    {
+        let mut distributor = account_distributor.data.borrow_mut();
+        let root: [u8; 32] = distributor[0..32].try_into().unwrap();
+        let count = u64::from_be_bytes(distributor[32..40].try_into().unwrap());
+        if index >= count || !merkle_verify(&root, merkle_leaf(mana.user.key, amount), index, &proof) {
+            return Err(AirdropError::InvalidProof.into());
+        }
+        let byte = DISTRIBUTOR_HEAD + (index / 8) as usize;
+        let mask = 1u8 << (index % 8);
+        if distributor[byte] & mask != 0 {
+            return Err(AirdropError::AlreadyClaimed.into());
+        }
+        distributor[byte] |= mask;
+    }
+
+    // 国库余额扣除本次领取后不能低于为归属计划保留的总量
+    let reserved = Config::load(account_config).reserved;
+    let required = reserved.checked_add(amount).ok_or(AirdropError::ReservedForVesting)?;
+    require(token_balance(mana.mana_spla)? >= required, AirdropError::ReservedForVesting)?;
+
+    // 从 Mana 向用户转账代币
+    mana.transfer(accounts, accounts_iter.as_slice(), amount)
+}
+
+/// 配置指令处理函数
+///
+/// 功能：创建或修改某个代币的空投配置
+/// - 首次调用时创建配置账户，要求签名者是该代币的铸造权限（mint authority），并成为管理员
+/// - 之后只有管理员可以修改
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表
+///   [0] 管理员账户（需要签名，可写）
+///   [1] 配置账户（PDA，种子为 [b"config", mint]，可写）
+///   [2] 代币铸造账户
+///   [3] System 程序
+/// - data: 依次为以下五个 8 字节整数（大端序）和 1 字节的模式
+///   每次空投的数量（u64，按最小单位计）
+///   两次领取之间至少间隔的 slot 数（u64，0 表示不限制）
+///   每个钱包累计可领取的上限（u64，按最小单位计，0 表示不限制）
+///   归属模式的锁仓期（i64，秒）
+///   归属模式的线性释放时长（i64，秒，0 表示锁仓期结束后一次性释放）
+///   模式（0 为直接空投，1 为归属模式；还有未释放的归属计划时不能关闭归属模式）
+pub fn process_instruction_config(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+    data: &[u8],
+) -> solana_program::entrypoint::ProgramResult {
+    // 解析账户列表
+    let accounts_iter = &mut accounts.iter();
+    let account_admin = solana_program::account_info::next_account_info(accounts_iter)?;         // 管理员账户
+    let account_config = solana_program::account_info::next_account_info(accounts_iter)?;        // 配置账户
+    let account_mint = solana_program::account_info::next_account_info(accounts_iter)?;          // 代币铸造账户
+    let account_system = solana_program::account_info::next_account_info(accounts_iter)?;        // System 程序
+
+    // 权限检查
+    require_signer(account_admin)?;
+    require_system_program(account_system)?;
+    let account_config_calc =
+        solana_program::pubkey::Pubkey::find_program_address(&[CONFIG_SEED, account_mint.key.as_ref()], program_id);
+    require(account_config.key == &account_config_calc.0, AirdropError::WrongConfig)?;
+    if data.len() != 41 || data[40] > 1 {
+        return Err(solana_program::program_error::ProgramError::InvalidInstructionData);
+    }
+    let amount = u64::from_be_bytes(data[0..8].try_into().unwrap());
+    let cooldown = u64::from_be_bytes(data[8..16].try_into().unwrap());
+    let cap = u64::from_be_bytes(data[16..24].try_into().unwrap());
+    let cliff = i64::from_be_bytes(data[24..32].try_into().unwrap());
+    let duration = i64::from_be_bytes(data[32..40].try_into().unwrap());
+    let vesting = data[40] == 1;
+    if cliff < 0 || duration < 0 {
+        return Err(solana_program::program_error::ProgramError::InvalidInstructionData);
+    }
+
+    // 以所有者判断配置是否存在：只看 lamports 的话，别人向该地址转入 1 lamport 就能永久阻止配置
+    let reserved = if account_config.owner != program_id {
+        // 首次创建：只有代币的铸造权限才能为该代币设置空投
+        require(
+            account_mint.owner == &spl_token::id() || account_mint.owner == &spl_token_2022::id(),
+            AirdropError::WrongTokenProgram,
+        )?;
+        let mint_data = account_mint.data.borrow();
+        let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+        require(
+            mint.base.mint_authority == solana_program::program_option::COption::Some(*account_admin.key),
+            AirdropError::Unauthorized,
+        )?;
+        drop(mint_data);
+
+        // 创建配置账户（使用 PDA 签名）
+        let bump = account_config_calc.1;
+        create_pda(
+            account_admin,
+            account_config,
+            CONFIG_SIZE,
+            program_id,
+            accounts,
+            &[CONFIG_SEED, account_mint.key.as_ref(), &[bump]],  // PDA 签名种子
+        )?;
+        0
+    } else {
+        // 修改配置：只有管理员可以修改
+        let config = Config::load(account_config);
+        require(account_admin.key == &config.admin, AirdropError::Unauthorized)?;
+        // 还有未释放的归属计划时不能切换到直接空投，否则空投会动用为归属计划保留的代币
+        require(vesting || config.reserved == 0, AirdropError::ReservedForVesting)?;
+        config.reserved
+    };
+
+    // 写入配置（保留尚未释放的归属总量）
+    Config { admin: *account_admin.key, amount, cooldown, cap, cliff, duration, vesting, reserved }.save(account_config);
+    Ok(())
+}
+
+/// 创建白名单分发指令处理函数
+///
+/// 功能：管理员为某个代币创建白名单分发账户，保存 Merkle 根和叶子数量，并分配已领取位图
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表
+///   [0] 管理员账户（需要签名，可写，与配置账户中的管理员一致）
+///   [1] 白名单分发账户（PDA，种子为 [b"distributor", mint]，可写）
+///   [2] 代币铸造账户
+///   [3] 配置账户（PDA，种子为 [b"config", mint]）
+///   [4] System 程序
+/// - data: Merkle 根（32 字节）+ 叶子数量（u64，大端序）
+pub fn process_instruction_distributor(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+    data: &[u8],
+) -> solana_program::entrypoint::ProgramResult {
+    // 解析账户列表
+    let accounts_iter = &mut accounts.iter();
+    let account_admin = solana_program::account_info::next_account_info(accounts_iter)?;         // 管理员账户
+    let account_distributor = solana_program::account_info::next_account_info(accounts_iter)?;   // 白名单分发账户
+    let account_mint = solana_program::account_info::next_account_info(accounts_iter)?;          // 代币铸造账户
+    let account_config = solana_program::account_info::next_account_info(accounts_iter)?;        // 配置账户
+    let account_system = solana_program::account_info::next_account_info(accounts_iter)?;        // System 程序
+
+    // 权限检查：只有配置账户中的管理员可以创建
+    require_signer(account_admin)?;
+    require_system_program(account_system)?;
+    let account_config_calc =
+        solana_program::pubkey::Pubkey::find_program_address(&[CONFIG_SEED, account_mint.key.as_ref()], program_id);
+    require(account_config.key == &account_config_calc.0, AirdropError::WrongConfig)?;
+    require(account_config.owner == program_id, AirdropError::WrongConfig)?;
+    require(account_admin.key == &Config::load(account_config).admin, AirdropError::Unauthorized)?;
+    let account_distributor_calc =
+        solana_program::pubkey::Pubkey::find_program_address(&[DISTRIBUTOR_SEED, account_mint.key.as_ref()], program_id);
+    require(account_distributor.key == &account_distributor_calc.0, AirdropError::WrongDistributor)?;
+
+    // 解析指令数据
+    if data.len() != 40 {
+        return Err(solana_program::program_error::ProgramError::InvalidInstructionData);
+    }
+    let count = u64::from_be_bytes(data[32..40].try_into().unwrap());
+
+    // 创建白名单分发账户（每个代币只能创建一次，已存在时 System 程序会拒绝；预先转入的 lamports 不影响创建）
+    let size = DISTRIBUTOR_HEAD + count.div_ceil(8) as usize;    // 头部 + 位图
+    create_pda(
+        account_admin,
+        account_distributor,
+        size,
+        program_id,
+        accounts,
+        &[DISTRIBUTOR_SEED, account_mint.key.as_ref(), &[account_distributor_calc.1]],
+    )?;
+    // 写入 Merkle 根和叶子数量，位图初始全为 0
+    account_distributor.data.borrow_mut()[0..40].copy_from_slice(data);
+    Ok(())
+}
+
+/// 国库类指令（提取、关闭）共用的账户，其后可以附加转账钩子需要的账户
+///   [0] 管理员账户（需要签名，可写，与配置账户中的管理员一致）
+///   [1] 接收代币的代币账户（可写）
+///   [2] Mana 的 PDA 权限账户（种子为 [&[]]）
+///   [3] Mana 的关联代币账户（可写）
+///   [4] 代币铸造账户
+///   [5] 配置账户（PDA，种子为 [b"config", mint]，关闭国库时可写）
+///   [6] 代币程序（SPL Token 或 SPL Token 2022，须与铸造账户所属程序一致）
+pub struct Treasury<'a, 'b> {
+    pub admin: &'a solana_program::account_info::AccountInfo<'b>,
+    pub into: &'a solana_program::account_info::AccountInfo<'b>,
+    pub mana_auth: &'a solana_program::account_info::AccountInfo<'b>,
+    pub mana_auth_bump: u8,
+    pub mana_spla: &'a solana_program::account_info::AccountInfo<'b>,
+    pub mint: &'a solana_program::account_info::AccountInfo<'b>,
+    pub config: &'a solana_program::account_info::AccountInfo<'b>,
+    pub spl: &'a solana_program::account_info::AccountInfo<'b>,
+    /// 尚未释放的归属总量，国库余额不能低于它
+    pub reserved: u64,
+}
+
+impl<'a, 'b> Treasury<'a, 'b> {
+    /// 从账户列表中依次取出账户并验证管理员权限
+    pub fn load(
+        program_id: &solana_program::pubkey::Pubkey,
+        accounts_iter: &mut std::slice::Iter<'a, solana_program::account_info::AccountInfo<'b>>,
+    ) -> Result<Self, solana_program::program_error::ProgramError> {
+        let account_admin = solana_program::account_info::next_account_info(accounts_iter)?;         // 管理员账户
+        let account_into = solana_program::account_info::next_account_info(accounts_iter)?;          // 接收代币的代币账户
+        let account_mana_auth = solana_program::account_info::next_account_info(accounts_iter)?;     // Mana 的 PDA 权限账户
+        let account_mana_spla = solana_program::account_info::next_account_info(accounts_iter)?;     // Mana 的关联代币账户
+        let account_mint = solana_program::account_info::next_account_info(accounts_iter)?;          // 代币铸造账户
+        let account_config = solana_program::account_info::next_account_info(accounts_iter)?;        // 配置账户
+        let account_spl = solana_program::account_info::next_account_info(accounts_iter)?;           // 代币程序
+
+        // 权限检查：只有配置账户中的管理员可以动用国库
+        require_signer(account_admin)?;
+        let account_config_calc =
+            solana_program::pubkey::Pubkey::find_program_address(&[CONFIG_SEED, account_mint.key.as_ref()], program_id);
+        require(account_config.key == &account_config_calc.0, AirdropError::WrongConfig)?;
+        require(account_config.owner == program_id, AirdropError::WrongConfig)?;
+        let config = Config::load(account_config);
+        require(account_admin.key == &config.admin, AirdropError::Unauthorized)?;
+
+        // 验证代币程序，以及 Mana 的 PDA 权限账户与关联代币账户
+        token_program_check(account_spl, account_mint)?;
+        let account_mana_auth_calc = solana_program::pubkey::Pubkey::find_program_address(&[&[]], program_id);
+        require(account_mana_auth.key == &account_mana_auth_calc.0, AirdropError::WrongManaPda)?;
+        let account_mana_spla_calc = spl_associated_token_account::get_associated_token_address_with_program_id(
+            account_mana_auth.key,
+            account_mint.key,
+            account_spl.key,
+        );
+        require(account_mana_spla.key == &account_mana_spla_calc, AirdropError::WrongManaAta)?;
+
+        Ok(Self {
+            admin: account_admin,
+            into: account_into,
+            mana_auth: account_mana_auth,
+            mana_auth_bump: account_mana_auth_calc.1,
+            mana_spla: account_mana_spla,
+            mint: account_mint,
+            config: account_config,
+            spl: account_spl,
+            reserved: config.reserved,
+        })
+    }
+
+    /// 国库当前的代币余额
+    pub fn balance(&self) -> Result<u64, solana_program::program_error::ProgramError> {
+        token_balance(self.mana_spla)
+    }
+
+    /// 从国库向接收账户转账（使用 PDA 签名），extra 为转账钩子需要的附加账户
+    pub fn withdraw(
+        &self,
+        extra: &[solana_program::account_info::AccountInfo<'b>],
+        amount: u64,
+    ) -> solana_program::entrypoint::ProgramResult {
+        let net = token_transfer(
+            self.spl,                           // SPL 程序
+            self.mana_spla,                     // 源账户（Mana 的代币账户）
+            self.mint,                          // 代币铸造账户
+            self.into,                          // 目标账户
+            self.mana_auth,                     // 转账权限（Mana 的 PDA）
+            extra,                              // 附加账户（转账钩子）
+            amount,                             // 转账数量
+            &[&[&[], &[self.mana_auth_bump]]],  // PDA 签名种子
+        )?;
+        solana_program::msg!("withdraw {} from mana, received {} after transfer fee", amount, net);
+        Ok(())
+    }
+}
+
+/// 提取指令处理函数
+///
+/// 功能：活动结束后，管理员把国库中的代币转到指定账户
+/// - 为归属计划保留的代币不能提取
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表，见 Treasury
+/// - data: 提取数量（u64，大端序，按最小单位计，0 表示提取全部未保留的代币）
+pub fn process_instruction_withdraw(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+    data: &[u8],
+) -> solana_program::entrypoint::ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let treasury = Treasury::load(program_id, accounts_iter)?;
+    let amount = u64::from_be_bytes(
+        data.try_into().map_err(|_| solana_program::program_error::ProgramError::InvalidInstructionData)?,
+    );
+    let available = treasury.balance()?.saturating_sub(treasury.reserved);
+    let amount = if amount == 0 { available } else { amount };
+    require(amount <= available, AirdropError::ReservedForVesting)?;
+    treasury.withdraw(accounts_iter.as_slice(), amount)
+}
+
+/// 关闭国库指令处理函数
+///
+/// 功能：把国库中剩余的代币全部转到指定账户，然后关闭 Mana 的关联代币账户和配置账户，租金退还给管理员
+/// - 还有未释放的归属计划时不能关闭
+/// - 关闭后需要由铸造权限重新配置，才能继续空投
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表，见 Treasury
+pub fn process_instruction_close(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+) -> solana_program::entrypoint::ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let treasury = Treasury::load(program_id, accounts_iter)?;
+    require(treasury.reserved == 0, AirdropError::ReservedForVesting)?;
+
+    // 先转出剩余代币，Token 程序只允许关闭余额为 0 的账户
+    let amount = treasury.balance()?;
+    if amount != 0 {
+        treasury.withdraw(accounts_iter.as_slice(), amount)?;
+    }
+
+    // 关闭 Mana 的关联代币账户（使用 PDA 签名）
+    solana_program::program::invoke_signed(
+        &token_close_account(
+            treasury.spl.key,               // SPL 程序
+            treasury.mana_spla.key,         // 要关闭的账户
+            treasury.admin.key,             // 租金接收者
+            treasury.mana_auth.key,         // 账户所有者（Mana 的 PDA）
+        )?,
+        accounts,
+        &[&[&[], &[treasury.mana_auth_bump]]],  // PDA 签名种子
+    )?;
+
+    // 关闭配置账户，租金退还给管理员
+    let lamports = treasury.config.lamports();
+    **treasury.config.try_borrow_mut_lamports()? = 0;
+    **treasury.admin.try_borrow_mut_lamports()? += lamports;
+    treasury.config.resize(0)?;
+    treasury.config.assign(&solana_program::system_program::id());
+    Ok(())
+}
+
+/// 补充国库指令处理函数
+///
+/// 功能：任何人都可以把自己的代币转入国库（Mana 的关联代币账户不存在时会自动创建），并在日志中留下记录
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表
+///   [0] 出资账户（需要签名，可写）
+///   [1] 出资账户的代币账户（可写）
+///   [2] Mana 的 PDA 权限账户（种子为 [&[]]）
+///   [3] Mana 的关联代币账户（可写）
+///   [4] 代币铸造账户
+///   [5] System 程序
+///   [6] 代币程序（SPL Token 或 SPL Token 2022，须与铸造账户所属程序一致）
+///   [7] 关联代币账户程序
+///   [8..] 转账钩子需要的附加账户（可选）
+/// - data: 补充数量（u64，大端序，按最小单位计）
+pub fn process_instruction_refill(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+    data: &[u8],
+) -> solana_program::entrypoint::ProgramResult {
+    // 解析账户列表
+    let accounts_iter = &mut accounts.iter();
+    let account_user = solana_program::account_info::next_account_info(accounts_iter)?;          // 出资账户
+    let account_user_spla = solana_program::account_info::next_account_info(accounts_iter)?;     // 出资账户的代币账户
     let account_mana_auth = solana_program::account_info::next_account_info(accounts_iter)?;     // Mana 的 PDA 权限账户
     let account_mana_spla = solana_program::account_info::next_account_info(accounts_iter)?;     // Mana 的关联代币账户
     let account_mint = solana_program::account_info::next_account_info(accounts_iter)?;          // 代币铸造账户
-    let _ = solana_program::account_info::next_account_info(accounts_iter)?;                     // 跳过账户
-    let account_spl = solana_program::account_info::next_account_info(accounts_iter)?;           // SPL Token 2022 程序
-    let _ = solana_program::account_info::next_account_info(accounts_iter)?;                     // 跳过账户
-
-    // ========== 2. 账户验证 ==========
-    // 验证用户签名
-    assert!(account_user.is_signer);
-    
-    // 验证用户的关联代币账户地址正确
-    let account_user_spla_calc = spl_associated_token_account::get_associated_token_address_with_program_id(
-        &account_user.key,
-        &account_mint.key,
-        &spl_token_2022::id(),
-    );
-    assert_eq!(account_user_spla.key, &account_user_spla_calc);
-    
-    // 验证 Mana 的 PDA 权限账户地址正确
-    let account_mana_auth_calc = solana_program::pubkey::Pubkey::find_program_address(&[&[]], account_mana.key);
-    assert_eq!(account_mana_auth.key, &account_mana_auth_calc.0);
-    
-    // 验证 Mana 的关联代币账户地址正确
+    let account_system = solana_program::account_info::next_account_info(accounts_iter)?;        // System 程序
+    let account_spl = solana_program::account_info::next_account_info(accounts_iter)?;           // 代币程序
+    let account_ata = solana_program::account_info::next_account_info(accounts_iter)?;           // 关联代币账户程序
+
+    // 账户验证
+    require_signer(account_user)?;
+    require_system_program(account_system)?;
+    require_ata_program(account_ata)?;
+    token_program_check(account_spl, account_mint)?;
+    let account_mana_auth_calc = solana_program::pubkey::Pubkey::find_program_address(&[&[]], program_id);
+    require(account_mana_auth.key == &account_mana_auth_calc.0, AirdropError::WrongManaPda)?;
     let account_mana_spla_calc = spl_associated_token_account::get_associated_token_address_with_program_id(
-        &account_mana_auth.key,
-        &account_mint.key,
-        &spl_token_2022::id(),
+        account_mana_auth.key,
+        account_mint.key,
+        account_spl.key,
+    );
+    require(account_mana_spla.key == &account_mana_spla_calc, AirdropError::WrongManaAta)?;
+    let amount = u64::from_be_bytes(
+        data.try_into().map_err(|_| solana_program::program_error::ProgramError::InvalidInstructionData)?,
     );
-    assert_eq!(account_mana_spla.key, &account_mana_spla_calc);
-    
-    // 验证 SPL 程序地址正确
-    assert_eq!(account_spl.key, &spl_token_2022::id());
 
-    // ========== 3. 创建用户的关联代币账户（如果不存在） ==========
+    // 创建 Mana 的关联代币账户（如果不存在，例如国库被关闭后重新开启）
     solana_program::program::invoke(
         &spl_associated_token_account::instruction::create_associated_token_account_idempotent(
-            &account_user.key,
-            &account_user.key,
-            &account_mint.key,
-            &account_spl.key,
+            account_user.key,
+            account_mana_auth.key,
+            account_mint.key,
+            account_spl.key,
         ),
         accounts,
     )?;
-    
-    // ========== 4. 从 Mana 向用户转账代币（使用 PDA 签名） ==========
-    solana_program::program::invoke_signed(
-        &spl_token_2022::instruction::transfer_checked(
-            &account_spl.key,               // SPL 程序
-            &account_mana_spla.key,         // 源账户（Mana 的代币账户）
-            &account_mint.key,              // 代币铸造账户
-            &account_user_spla.key,         // 目标账户（用户的代币账户）
-            &account_mana_auth.key,         // 转账权限（Mana 的 PDA）
-            &[],                            // 多签账户（无）
-            5000000000,                     // 转账数量（5 * 10^9，按 9 位小数）
-            9,                              // 小数位数
-        )?,
-        accounts,
-        &[&[&[], &[account_mana_auth_calc.1]]],  // PDA 签名种子
+
+    // 出资账户向国库转账（出资者自己签名）
+    let net = token_transfer(
+        account_spl,                        // SPL 程序
+        account_user_spla,                  // 源账户（出资账户的代币账户）
+        account_mint,                       // 代币铸造账户
+        account_mana_spla,                  // 目标账户（Mana 的代币账户）
+        account_user,                       // 转账权限（出资者）
+        accounts_iter.as_slice(),           // 附加账户（转账钩子）
+        amount,                             // 转账数量
+        &[],                                // 无需 PDA 签名
     )?;
+    solana_program::msg!("refill {} into mana, received {} after transfer fee", amount, net);
+    Ok(())
+}
+
+/// 创建归属计划指令处理函数
+///
+/// 功能：与空投指令一样受冷却时间与累计上限约束，但不立即转账，而是为用户创建归属计划
+/// - 只能在归属模式下调用
+/// - 开始时间取自 Clock 系统变量，锁仓期和线性释放时长取自配置账户
+/// - 代币仍留在 Mana 的关联代币账户中，计入配置中的保留总量，由释放指令按解锁进度转给用户
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表
+///   [0..9] 见 Mana
+///   [9] 配置账户（PDA，种子为 [b"config", mint]，可写）
+///   [10] 领取记录账户（PDA，种子为 [b"claim", user, mint]，可写，首次领取时创建）
+///   [11] 归属计划账户（PDA，种子为 [b"vesting", user, mint]，可写）
+pub fn process_instruction_vest(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+) -> solana_program::entrypoint::ProgramResult {
+    // 解析并验证账户
+    let accounts_iter = &mut accounts.iter();
+    let mana = Mana::load(program_id, accounts_iter)?;
+    let account_config = accounts_iter.as_slice().first().ok_or(solana_program::program_error::ProgramError::NotEnoughAccountKeys)?;
+    let mut config = claim(program_id, accounts, accounts_iter, &mana)?;
+    require(config.vesting, AirdropError::WrongMode)?;
+    let account_vesting = solana_program::account_info::next_account_info(accounts_iter)?;       // 归属计划账户
+    let account_vesting_calc = solana_program::pubkey::Pubkey::find_program_address(
+        &[VESTING_SEED, mana.user.key.as_ref(), mana.mint.key.as_ref()],
+        program_id,
+    );
+    require(account_vesting.key == &account_vesting_calc.0, AirdropError::WrongVesting)?;
+    // 以所有者判断归属计划是否存在，预先转入 lamports 不能阻止创建
+    require(account_vesting.owner != program_id, AirdropError::VestingExists)?;
+
+    // 为归属计划保留代币：国库余额必须覆盖所有未释放的归属总量
+    let reserved = config.reserved.checked_add(config.amount).ok_or(AirdropError::ReservedForVesting)?;
+    require(token_balance(mana.mana_spla)? >= reserved, AirdropError::ReservedForVesting)?;
+    config.reserved = reserved;
+    config.save(account_config);
 
+    // 创建归属计划账户（由用户支付租金）
+    create_pda(
+        mana.user,
+        account_vesting,
+        VESTING_SIZE,
+        program_id,
+        accounts,
+        &[VESTING_SEED, mana.user.key.as_ref(), mana.mint.key.as_ref(), &[account_vesting_calc.1]],
+    )?;
+    Vesting {
+        total: config.amount,
+        released: 0,
+        start: solana_program::clock::Clock::get()?.unix_timestamp,
+        cliff: config.cliff,
+        duration: config.duration,
+    }
+    .save(account_vesting);
     Ok(())
 }
+
+/// 释放指令处理函数
+///
+/// 功能：把归属计划中已解锁但尚未释放的代币从 Mana 转给用户
+/// - 同时从配置中的保留总量里扣除本次释放的数量
+/// - 全部释放后关闭归属计划账户，租金退还给用户，之后可以再次创建归属计划
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表
+///   [0..9] 见 Mana
+///   [9] 配置账户（PDA，种子为 [b"config", mint]，可写）
+///   [10] 归属计划账户（PDA，种子为 [b"vesting", user, mint]，可写）
+///   [11..] 转账钩子需要的附加账户（可选）
+pub fn process_instruction_release(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+) -> solana_program::entrypoint::ProgramResult {
+    // 解析并验证账户
+    let accounts_iter = &mut accounts.iter();
+    let mana = Mana::load(program_id, accounts_iter)?;
+    let account_config = solana_program::account_info::next_account_info(accounts_iter)?;        // 配置账户
+    let account_vesting = solana_program::account_info::next_account_info(accounts_iter)?;       // 归属计划账户
+    let account_config_calc =
+        solana_program::pubkey::Pubkey::find_program_address(&[CONFIG_SEED, mana.mint.key.as_ref()], program_id);
+    require(account_config.key == &account_config_calc.0, AirdropError::WrongConfig)?;
+    require(account_config.owner == program_id, AirdropError::WrongConfig)?;
+    let account_vesting_calc = solana_program::pubkey::Pubkey::find_program_address(
+        &[VESTING_SEED, mana.user.key.as_ref(), mana.mint.key.as_ref()],
+        program_id,
+    );
+    require(account_vesting.key == &account_vesting_calc.0, AirdropError::WrongVesting)?;
+    require(account_vesting.owner == program_id, AirdropError::WrongVesting)?;
+
+    // 计算本次可释放的数量并记账
+    let mut vesting = Vesting::load(account_vesting);
+    let now = solana_program::clock::Clock::get()?.unix_timestamp;
+    let amount = vesting.unlocked(now).saturating_sub(vesting.released);
+    require(amount != 0, AirdropError::NothingToRelease)?;
+    vesting.released += amount;
+    let mut config = Config::load(account_config);
+    config.reserved = config.reserved.saturating_sub(amount);
+    config.save(account_config);
+
+    if vesting.released == vesting.total {
+        // 全部释放：关闭归属计划账户，租金退还给用户
+        let lamports = account_vesting.lamports();
+        **account_vesting.try_borrow_mut_lamports()? = 0;
+        **mana.user.try_borrow_mut_lamports()? += lamports;
+        account_vesting.resize(0)?;
+        account_vesting.assign(&solana_program::system_program::id());
+    } else {
+        vesting.save(account_vesting);
+    }
+
+    // 从 Mana 向用户转账代币
+    mana.transfer(accounts, accounts_iter.as_slice(), amount)
+}
+
+/// 程序主入口函数（指令路由器）
+///
+/// 根据指令数据的第一个字节来决定执行哪个操作：
+/// - 空数据或 0x00: 空投操作
+///   注意这是不兼容的变更：空投现在必须额外传入 [9] 配置账户和 [10] 领取记录账户，
+///   只发送 9 个账户的旧客户端会因账户不足而失败，需要更新
+/// - 0x01: 创建或修改空投配置
+/// - 0x02: 创建白名单分发
+/// - 0x03: 凭 Merkle 证明领取白名单空投
+/// - 0x04: 管理员从国库提取代币
+/// - 0x05: 管理员关闭国库
+/// - 0x06: 补充国库
+/// - 0x07: 创建归属计划
+/// - 0x08: 释放归属计划中已解锁的代币
+///
+/// 参数：
+/// - program_id: 当前程序的公钥
+/// - accounts: 账户列表（具体内容由子指令决定）
+/// - data: 指令数据（第一个字节为指令类型，后续字节为指令参数）
+pub fn process_instruction(
+    program_id: &solana_program::pubkey::Pubkey,
+    accounts: &[solana_program::account_info::AccountInfo],
+    data: &[u8],
+) -> solana_program::entrypoint::ProgramResult {
+    match data.first() {
+        None | Some(0x00) => process_instruction_airdrop(program_id, accounts),  // 空投指令（空数据与 0x00 相同）
+        Some(0x01) => process_instruction_config(program_id, accounts, &data[1..]),  // 配置指令
+        Some(0x02) => process_instruction_distributor(program_id, accounts, &data[1..]),  // 创建白名单分发
+        Some(0x03) => process_instruction_merkle_claim(program_id, accounts, &data[1..]),  // 白名单领取
+        Some(0x04) => process_instruction_withdraw(program_id, accounts, &data[1..]),  // 提取
+        Some(0x05) => process_instruction_close(program_id, accounts),  // 关闭国库
+        Some(0x06) => process_instruction_refill(program_id, accounts, &data[1..]),  // 补充国库
+        Some(0x07) => process_instruction_vest(program_id, accounts),  // 创建归属计划
+        Some(0x08) => process_instruction_release(program_id, accounts),  // 释放
+        _ => Err(solana_program::program_error::ProgramError::InvalidInstructionData),  // 其他值视为无效指令
+    }
+}
@@ -0,0 +1,771 @@
+// 在进程内的 SVM 中测试空投程序（LiteSVM 默认已加载 SPL Token、SPL Token 2022 与关联代币账户程序）
+//
+// 运行方式：
+//   cargo build-sbf   # 生成 target/deploy/pxsol_spl.so
+//   cargo test
+//
+// 如果还没有 cargo build-sbf，测试会直接失败并提示先编译
+
+use std::path::Path;
+
+use litesvm::types::TransactionResult;
+use litesvm::LiteSVM;
+use pxsol_spl::{AirdropError, CLAIM_SEED, CONFIG_SEED, CONFIG_SIZE, DISTRIBUTOR_SEED, VESTING_SEED};
+use solana_keypair::Keypair;
+use solana_program::clock::Clock;
+use solana_program::instruction::{AccountMeta, Instruction};
+use solana_program::pubkey::Pubkey;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+use spl_token_2022::extension::transfer_fee::TransferFeeAmount;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+
+const PXSOL_SO: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/deploy/pxsol_spl.so");
+
+/// 每次空投的数量
+const AMOUNT: u64 = 5_000_000_000;
+/// 预先存入 Mana 关联代币账户的数量
+const SUPPLY: u64 = 100_000_000_000;
+
+// ============================================
+// 测试辅助函数
+// ============================================
+
+/// 测试环境：SVM、空投程序、铸造权限（同时是空投管理员）、代币铸造账户及其所属的代币程序
+struct Env {
+    svm: LiteSVM,
+    mana: Pubkey,
+    admin: Keypair,
+    mint: Pubkey,
+    spl: Pubkey,
+}
+
+/// 创建 SVM 并加载空投程序，然后创建 Token-2022 代币、为 Mana 的关联代币账户铸币并写入空投配置
+fn setup() -> Env {
+    let mut env = setup_without_config();
+    // 写入空投配置：不设冷却时间和累计上限
+    let ix = env.ix_config(AMOUNT, 0, 0, false);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    env
+}
+
+/// 与 setup 相同，但不写入空投配置
+fn setup_without_config() -> Env {
+    setup_mint(spl_token_2022::id(), None)
+}
+
+/// 创建 SVM 并加载空投程序，然后在代币程序 spl（SPL Token 或 SPL Token 2022）下创建代币并为 Mana 的关联代币账户铸币
+/// - transfer_fee 为 Some((基点, 最高手续费)) 时为 Token-2022 铸造账户启用 TransferFee 扩展
+fn setup_mint(spl: Pubkey, transfer_fee: Option<(u16, u64)>) -> Env {
+    assert!(Path::new(PXSOL_SO).exists(), "未找到 {PXSOL_SO}，请先运行 cargo build-sbf");
+    let mut svm = LiteSVM::new();
+    let mana = Pubkey::new_unique();
+    svm.add_program_from_file(mana, PXSOL_SO).unwrap();
+    let admin = Keypair::new();
+    svm.airdrop(&admin.pubkey(), 10_000_000_000).unwrap();
+
+    // 创建铸造账户（两个代币程序的铸造账户基础布局相同，Token-2022 的指令构造函数也接受 SPL Token 的程序 ID）
+    let mint = Keypair::new();
+    let extensions = if transfer_fee.is_some() { vec![ExtensionType::TransferFeeConfig] } else { vec![] };
+    let size = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions).unwrap();
+    let rent = svm.minimum_balance_for_rent_exemption(size);
+    let mut ixs = vec![solana_program::system_instruction::create_account(
+        &admin.pubkey(),
+        &mint.pubkey(),
+        rent,
+        size as u64,
+        &spl,
+    )];
+    // 扩展必须在 initialize_mint2 之前初始化
+    if let Some((basis_points, maximum_fee)) = transfer_fee {
+        ixs.push(
+            spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+                &spl,
+                &mint.pubkey(),
+                None,
+                None,
+                basis_points,
+                maximum_fee,
+            )
+            .unwrap(),
+        );
+    }
+    ixs.push(spl_token_2022::instruction::initialize_mint2(&spl, &mint.pubkey(), &admin.pubkey(), None, 9).unwrap());
+    send(&mut svm, &[&admin, &mint], &ixs).unwrap();
+    let mint = mint.pubkey();
+
+    // 创建 Mana 的关联代币账户并铸币
+    let mut env = Env { svm, mana, admin, mint, spl };
+    let mana_auth = env.mana_auth();
+    let mana_spla = env.ata(&mana_auth);
+    let ixs = [
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &env.admin.pubkey(),
+            &mana_auth,
+            &env.mint,
+            &spl,
+        ),
+        spl_token_2022::instruction::mint_to(&spl, &env.mint, &mana_spla, &env.admin.pubkey(), &[], SUPPLY).unwrap(),
+    ];
+    send(&mut env.svm, &[&env.admin], &ixs).unwrap();
+    env
+}
+
+/// 把多条指令打包成一笔交易发送，第一个签名者支付手续费
+// 返回类型沿用 LiteSVM 的 TransactionResult，不为了 clippy 再包一层 Box
+#[allow(clippy::result_large_err)]
+fn send(svm: &mut LiteSVM, signers: &[&Keypair], ixs: &[Instruction]) -> TransactionResult {
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&signers[0].pubkey()), signers, svm.latest_blockhash());
+    let result = svm.send_transaction(tx);
+    // 刷新 blockhash，连续发送相同的指令时不会被判为重复交易
+    svm.expire_blockhash();
+    result
+}
+
+impl Env {
+    fn mana_auth(&self) -> Pubkey {
+        Pubkey::find_program_address(&[&[]], &self.mana).0
+    }
+
+    fn ata(&self, owner: &Pubkey) -> Pubkey {
+        spl_associated_token_account::get_associated_token_address_with_program_id(owner, &self.mint, &self.spl)
+    }
+
+    fn config(&self) -> Pubkey {
+        Pubkey::find_program_address(&[CONFIG_SEED, self.mint.as_ref()], &self.mana).0
+    }
+
+    fn claim(&self, user: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[CLAIM_SEED, user.as_ref(), self.mint.as_ref()], &self.mana).0
+    }
+
+    /// 创建用户并转入 SOL 以支付关联代币账户和领取记录的租金
+    fn user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), 1_000_000_000).unwrap();
+        user
+    }
+
+    fn vesting(&self, user: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[VESTING_SEED, user.as_ref(), self.mint.as_ref()], &self.mana).0
+    }
+
+    /// 指令 0x01：创建或修改空投配置，归属模式下锁仓期和释放时长均为 0（创建后即可全部释放）
+    fn ix_config(&self, amount: u64, cooldown: u64, cap: u64, vesting: bool) -> Instruction {
+        let mut data = vec![0x01];
+        data.extend_from_slice(&amount.to_be_bytes());
+        data.extend_from_slice(&cooldown.to_be_bytes());
+        data.extend_from_slice(&cap.to_be_bytes());
+        data.extend_from_slice(&0i64.to_be_bytes());
+        data.extend_from_slice(&0i64.to_be_bytes());
+        data.push(vesting as u8);
+        Instruction {
+            program_id: self.mana,
+            accounts: vec![
+                AccountMeta::new(self.admin.pubkey(), true),
+                AccountMeta::new(self.config(), false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            ],
+            data,
+        }
+    }
+
+    /// 指令 0x01：切换到归属模式，并设置锁仓期和线性释放时长（秒）
+    fn ix_config_schedule(&self, amount: u64, cliff: i64, duration: i64) -> Instruction {
+        let mut ix = self.ix_config(amount, 0, 0, true);
+        ix.data[25..33].copy_from_slice(&cliff.to_be_bytes());
+        ix.data[33..41].copy_from_slice(&duration.to_be_bytes());
+        ix
+    }
+
+    /// 把时钟调到 timestamp（unix 时间戳，秒）
+    fn warp_to_timestamp(&mut self, timestamp: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp = timestamp;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// 指令 0x00：空投，mana_spla 为传入的 Mana 关联代币账户地址
+    fn ix_airdrop(&self, user: &Pubkey, mana_spla: Pubkey) -> Instruction {
+        Instruction {
+            program_id: self.mana,
+            accounts: vec![
+                AccountMeta::new(*user, true),
+                AccountMeta::new(self.ata(user), false),
+                AccountMeta::new_readonly(self.mana, false),
+                AccountMeta::new_readonly(self.mana_auth(), false),
+                AccountMeta::new(mana_spla, false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                AccountMeta::new_readonly(self.spl, false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                AccountMeta::new_readonly(self.config(), false),
+                AccountMeta::new(self.claim(user), false),
+            ],
+            data: vec![0x00],
+        }
+    }
+
+    fn distributor(&self) -> Pubkey {
+        Pubkey::find_program_address(&[DISTRIBUTOR_SEED, self.mint.as_ref()], &self.mana).0
+    }
+
+    /// 指令 0x02：创建白名单分发账户
+    fn ix_distributor(&self, root: [u8; 32], count: u64) -> Instruction {
+        let mut data = vec![0x02];
+        data.extend_from_slice(&root);
+        data.extend_from_slice(&count.to_be_bytes());
+        Instruction {
+            program_id: self.mana,
+            accounts: vec![
+                AccountMeta::new(self.admin.pubkey(), true),
+                AccountMeta::new(self.distributor(), false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new_readonly(self.config(), false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            ],
+            data,
+        }
+    }
+
+    /// 指令 0x03：白名单领取，前 9 个账户与空投相同
+    fn ix_merkle_claim(&self, user: &Pubkey, index: u64, amount: u64, proof: &[[u8; 32]]) -> Instruction {
+        let mut ix = self.ix_airdrop(user, self.ata(&self.mana_auth()));
+        ix.accounts.truncate(9);
+        ix.accounts.push(AccountMeta::new_readonly(self.config(), false));
+        ix.accounts.push(AccountMeta::new(self.distributor(), false));
+        ix.data = vec![0x03];
+        ix.data.extend_from_slice(&index.to_be_bytes());
+        ix.data.extend_from_slice(&amount.to_be_bytes());
+        for node in proof {
+            ix.data.extend_from_slice(node);
+        }
+        ix
+    }
+
+    /// 指令 0x07 / 0x08：创建归属计划或释放，前 9 个账户与空投相同
+    fn ix_vesting(&self, user: &Pubkey, tag: u8) -> Instruction {
+        let mut ix = self.ix_airdrop(user, self.ata(&self.mana_auth()));
+        ix.accounts.truncate(9);
+        ix.accounts.push(AccountMeta::new(self.config(), false));
+        if tag == 0x07 {
+            ix.accounts.push(AccountMeta::new(self.claim(user), false));
+        }
+        ix.accounts.push(AccountMeta::new(self.vesting(user), false));
+        ix.data = vec![tag];
+        ix
+    }
+
+    /// 指令 0x04 / 0x05：提取或关闭国库，代币转入 into（关闭国库时会关闭配置账户，因此配置账户可写）
+    fn ix_treasury(&self, into: Pubkey, data: Vec<u8>) -> Instruction {
+        Instruction {
+            program_id: self.mana,
+            accounts: vec![
+                AccountMeta::new(self.admin.pubkey(), true),
+                AccountMeta::new(into, false),
+                AccountMeta::new_readonly(self.mana_auth(), false),
+                AccountMeta::new(self.ata(&self.mana_auth()), false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new(self.config(), false),
+                AccountMeta::new_readonly(self.spl, false),
+            ],
+            data,
+        }
+    }
+
+    /// 指令 0x06：出资者把自己关联代币账户中的 amount 个代币转入国库
+    fn ix_refill(&self, funder: &Pubkey, amount: u64) -> Instruction {
+        let mut data = vec![0x06];
+        data.extend_from_slice(&amount.to_be_bytes());
+        Instruction {
+            program_id: self.mana,
+            accounts: vec![
+                AccountMeta::new(*funder, true),
+                AccountMeta::new(self.ata(funder), false),
+                AccountMeta::new_readonly(self.mana_auth(), false),
+                AccountMeta::new(self.ata(&self.mana_auth()), false),
+                AccountMeta::new_readonly(self.mint, false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+                AccountMeta::new_readonly(self.spl, false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            ],
+            data,
+        }
+    }
+
+    /// 读取配置账户中尚未释放的归属总量（配置末尾 8 字节，大端序）
+    fn reserved(&self) -> u64 {
+        let data = self.svm.get_account(&self.config()).unwrap().data;
+        u64::from_be_bytes(data[CONFIG_SIZE - 8..].try_into().unwrap())
+    }
+
+    /// 读取代币账户的余额
+    fn balance(&self, account: &Pubkey) -> u64 {
+        let account = self.svm.get_account(account).unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).unwrap().base.amount
+    }
+}
+
+// ============================================
+// 测试用例
+// ============================================
+
+#[test]
+fn test_airdrop_balance() {
+    let mut env = setup();
+    let user = env.user();
+    let mana_spla = env.ata(&env.mana_auth());
+
+    // 用户的关联代币账户尚不存在，由空投指令创建
+    assert!(env.svm.get_account(&env.ata(&user.pubkey())).is_none());
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+    assert_eq!(env.balance(&mana_spla), SUPPLY - AMOUNT);
+}
+
+#[test]
+fn test_airdrop_user_ata_idempotent() {
+    let mut env = setup();
+    let user = env.user();
+    let mana_spla = env.ata(&env.mana_auth());
+
+    // 第二次空投时用户的关联代币账户已经存在，创建指令不应失败
+    for _ in 0..2 {
+        let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+        send(&mut env.svm, &[&user], &[ix]).unwrap();
+    }
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT * 2);
+    assert_eq!(env.balance(&mana_spla), SUPPLY - AMOUNT * 2);
+}
+
+#[test]
+fn test_airdrop_spoofed_mana_ata() {
+    let mut env = setup();
+    let user = env.user();
+
+    // 攻击者自己的关联代币账户，冒充 Mana 的关联代币账户
+    let attacker = env.user();
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &attacker.pubkey(),
+        &attacker.pubkey(),
+        &env.mint,
+        &spl_token_2022::id(),
+    );
+    send(&mut env.svm, &[&attacker], &[ix]).unwrap();
+    let spoofed = env.ata(&attacker.pubkey());
+
+    let ix = env.ix_airdrop(&user.pubkey(), spoofed);
+    let result = send(&mut env.svm, &[&user], &[ix]);
+    let err = format!("{:?}", result.unwrap_err().err);
+    assert!(err.contains(&format!("Custom({})", AirdropError::WrongManaAta as u32)), "unexpected error: {err}");
+    assert!(env.svm.get_account(&env.ata(&user.pubkey())).is_none());
+}
+
+#[test]
+fn test_config_prefunded() {
+    let mut env = setup_without_config();
+
+    // 任何人都可以向配置账户地址预先转入 lamports，不能因此阻止配置
+    let config = env.config();
+    env.svm.airdrop(&config, 1).unwrap();
+    let ix = env.ix_config(AMOUNT, 0, 0, false);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+
+    let account = env.svm.get_account(&config).unwrap();
+    assert_eq!(account.owner, env.mana);
+    assert_eq!(account.data.len(), CONFIG_SIZE);
+    assert_eq!(account.lamports, env.svm.minimum_balance_for_rent_exemption(CONFIG_SIZE));
+
+    // 配置生效后可以正常空投
+    let user = env.user();
+    let mana_spla = env.ata(&env.mana_auth());
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+}
+
+#[test]
+fn test_airdrop_prefunded_claim_record() {
+    let mut env = setup();
+    let user = env.user();
+    let mana_spla = env.ata(&env.mana_auth());
+
+    // 攻击者向用户的领取记录地址预先转入 lamports，用户仍然可以领取
+    let claim = env.claim(&user.pubkey());
+    env.svm.airdrop(&claim, 1).unwrap();
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+    assert_eq!(env.svm.get_account(&claim).unwrap().owner, env.mana);
+}
+
+/// 由白名单构造 Merkle 树，返回从叶子到根的每一层（与 make.py 相同，奇数个节点时最后一个与自身配对）
+fn merkle_tree(list: &[(Pubkey, u64)]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![list.iter().map(|(user, amount)| pxsol_spl::merkle_leaf(user, *amount)).collect::<Vec<_>>()];
+    while levels.last().unwrap().len() > 1 {
+        let level = levels.last().unwrap();
+        let next = level
+            .chunks(2)
+            .map(|pair| solana_program::hash::hashv(&[&pair[0], pair.get(1).unwrap_or(&pair[0])]).to_bytes())
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// 取出第 index 个叶子的证明节点（从叶子到根）
+fn merkle_proof(levels: &[Vec<[u8; 32]>], index: usize) -> Vec<[u8; 32]> {
+    let mut index = index;
+    let mut proof = vec![];
+    for level in &levels[..levels.len() - 1] {
+        proof.push(*level.get(index ^ 1).unwrap_or(&level[index]));
+        index >>= 1;
+    }
+    proof
+}
+
+/// 断言交易以指定的 AirdropError 失败
+fn assert_airdrop_error(result: TransactionResult, expected: AirdropError) {
+    let err = format!("{:?}", result.unwrap_err().err);
+    assert!(err.contains(&format!("Custom({})", expected as u32)), "unexpected error: {err}");
+}
+
+#[test]
+fn test_wrong_mode() {
+    let mut env = setup();
+    let user = env.user();
+
+    // 直接空投模式下不能创建归属计划
+    let ix = env.ix_vesting(&user.pubkey(), 0x07);
+    assert_airdrop_error(send(&mut env.svm, &[&user], &[ix]), AirdropError::WrongMode);
+
+    // 切换到归属模式后不能直接空投，否则可以绕过锁仓
+    let ix = env.ix_config(AMOUNT, 0, 0, true);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let ix = env.ix_airdrop(&user.pubkey(), env.ata(&env.mana_auth()));
+    assert_airdrop_error(send(&mut env.svm, &[&user], &[ix]), AirdropError::WrongMode);
+}
+
+#[test]
+fn test_vesting_reserved() {
+    let mut env = setup();
+    let ix = env.ix_config(AMOUNT, 0, 0, true);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let user = env.user();
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &user.pubkey(),
+        &user.pubkey(),
+        &env.mint,
+        &spl_token_2022::id(),
+    );
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+
+    // 预先转入 lamports 不能阻止创建归属计划
+    let vesting = env.vesting(&user.pubkey());
+    env.svm.airdrop(&vesting, 1).unwrap();
+    let ix = env.ix_vesting(&user.pubkey(), 0x07);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.reserved(), AMOUNT);
+
+    // 为归属计划保留的代币不能提取，也不能关闭国库或切回直接空投模式
+    let into = env.ata(&env.admin.pubkey());
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &env.admin.pubkey(),
+        &env.admin.pubkey(),
+        &env.mint,
+        &spl_token_2022::id(),
+    );
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let mut data = vec![0x04];
+    data.extend_from_slice(&(SUPPLY - AMOUNT + 1).to_be_bytes());
+    let ix = env.ix_treasury(into, data);
+    assert_airdrop_error(send(&mut env.svm, &[&env.admin], &[ix]), AirdropError::ReservedForVesting);
+    let ix = env.ix_treasury(into, vec![0x05]);
+    assert_airdrop_error(send(&mut env.svm, &[&env.admin], &[ix]), AirdropError::ReservedForVesting);
+    let ix = env.ix_config(AMOUNT, 0, 0, false);
+    assert_airdrop_error(send(&mut env.svm, &[&env.admin], &[ix]), AirdropError::ReservedForVesting);
+
+    // 提取全部（数量为 0）只取走未保留的部分
+    let ix = env.ix_treasury(into, [0x04].into_iter().chain(0u64.to_be_bytes()).collect());
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    assert_eq!(env.balance(&into), SUPPLY - AMOUNT);
+    assert_eq!(env.balance(&env.ata(&env.mana_auth())), AMOUNT);
+
+    // 全部释放后归属计划账户关闭，保留总量归零
+    let ix = env.ix_vesting(&user.pubkey(), 0x08);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+    assert_eq!(env.reserved(), 0);
+    assert!(env.svm.get_account(&vesting).is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_merkle_claim_reserved() {
+    let mut env = setup();
+    let ix = env.ix_config(AMOUNT, 0, 0, true);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let user = env.user();
+    let ix = env.ix_vesting(&user.pubkey(), 0x07);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.reserved(), AMOUNT);
+
+    // 白名单中 a 的数量会动用为归属计划保留的代币，b 恰好取完未保留的部分
+    let a = env.user();
+    let b = env.user();
+    let list = [(a.pubkey(), SUPPLY - AMOUNT + 1), (b.pubkey(), SUPPLY - AMOUNT)];
+    let levels = merkle_tree(&list);
+    let ix = env.ix_distributor(levels.last().unwrap()[0], list.len() as u64);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+
+    let ix = env.ix_merkle_claim(&a.pubkey(), 0, list[0].1, &merkle_proof(&levels, 0));
+    assert_airdrop_error(send(&mut env.svm, &[&a], &[ix]), AirdropError::ReservedForVesting);
+    let ix = env.ix_merkle_claim(&b.pubkey(), 1, list[1].1, &merkle_proof(&levels, 1));
+    send(&mut env.svm, &[&b], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&b.pubkey())), SUPPLY - AMOUNT);
+    assert_eq!(env.balance(&env.ata(&env.mana_auth())), AMOUNT);
+
+    // 保留的代币仍然可以全部释放给归属计划的用户
+    let ix = env.ix_vesting(&user.pubkey(), 0x08);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+}
+
+#[test]
+fn test_vesting_schedule() {
+    let mut env = setup();
+    let ix = env.ix_config_schedule(AMOUNT, 100, 1000);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let user = env.user();
+    let start = env.svm.get_sysvar::<Clock>().unix_timestamp;
+    let ix = env.ix_vesting(&user.pubkey(), 0x07);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    let vesting = env.vesting(&user.pubkey());
+
+    // 锁仓期内没有可释放的代币
+    env.warp_to_timestamp(start + 50);
+    let ix = env.ix_vesting(&user.pubkey(), 0x08);
+    assert_airdrop_error(send(&mut env.svm, &[&user], &[ix]), AirdropError::NothingToRelease);
+
+    // 锁仓期结束后按已经过的时间比例释放
+    env.warp_to_timestamp(start + 250);
+    let ix = env.ix_vesting(&user.pubkey(), 0x08);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT / 4);
+    assert_eq!(env.reserved(), AMOUNT - AMOUNT / 4);
+    assert!(env.svm.get_account(&vesting).is_some_and(|account| account.lamports > 0));
+
+    // 释放时长结束后释放剩余部分，归属计划账户关闭
+    env.warp_to_timestamp(start + 1000);
+    let ix = env.ix_vesting(&user.pubkey(), 0x08);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+    assert_eq!(env.reserved(), 0);
+    assert!(env.svm.get_account(&vesting).is_none_or(|account| account.lamports == 0));
+}
+
+#[test]
+fn test_merkle_claim() {
+    let mut env = setup();
+    let users = [env.user(), env.user(), env.user()];
+    let list: Vec<(Pubkey, u64)> =
+        users.iter().enumerate().map(|(i, u)| (u.pubkey(), AMOUNT * (i as u64 + 1))).collect();
+    let levels = merkle_tree(&list);
+    let root = levels.last().unwrap()[0];
+
+    // 只有管理员可以创建白名单分发账户
+    let attacker = env.user();
+    let mut ix = env.ix_distributor(root, list.len() as u64);
+    ix.accounts[0] = AccountMeta::new(attacker.pubkey(), true);
+    assert_airdrop_error(send(&mut env.svm, &[&attacker], &[ix]), AirdropError::Unauthorized);
+
+    // 分发账户依次保存 Merkle 根、叶子数量和位图
+    let ix = env.ix_distributor(root, list.len() as u64);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let account = env.svm.get_account(&env.distributor()).unwrap();
+    assert_eq!(account.owner, env.mana);
+    assert_eq!(account.data[0..32], root);
+    assert_eq!(account.data[32..40], (list.len() as u64).to_be_bytes());
+    assert_eq!(account.data[40..], [0]);
+
+    // 有效的证明
+    let ix = env.ix_merkle_claim(&users[0].pubkey(), 0, list[0].1, &merkle_proof(&levels, 0));
+    send(&mut env.svm, &[&users[0]], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&users[0].pubkey())), list[0].1);
+
+    // 同一个序号不能重复领取
+    let ix = env.ix_merkle_claim(&users[0].pubkey(), 0, list[0].1, &merkle_proof(&levels, 0));
+    assert_airdrop_error(send(&mut env.svm, &[&users[0]], &[ix]), AirdropError::AlreadyClaimed);
+
+    // 数量与白名单不符、使用别人的证明或序号不对都会被拒绝
+    let ix = env.ix_merkle_claim(&users[1].pubkey(), 1, list[1].1 + 1, &merkle_proof(&levels, 1));
+    assert_airdrop_error(send(&mut env.svm, &[&users[1]], &[ix]), AirdropError::InvalidProof);
+    let ix = env.ix_merkle_claim(&users[1].pubkey(), 2, list[2].1, &merkle_proof(&levels, 2));
+    assert_airdrop_error(send(&mut env.svm, &[&users[1]], &[ix]), AirdropError::InvalidProof);
+    let ix = env.ix_merkle_claim(&users[1].pubkey(), 0, list[1].1, &merkle_proof(&levels, 1));
+    assert_airdrop_error(send(&mut env.svm, &[&users[1]], &[ix]), AirdropError::InvalidProof);
+
+    // 叶子数量为奇数时最后一个叶子与自身配对，序号 3 也能算出同一个根，必须按叶子数量拒绝，
+    // 否则最后一个用户可以用序号 2 和 3 各领取一次
+    let ix = env.ix_merkle_claim(&users[2].pubkey(), 3, list[2].1, &merkle_proof(&levels, 3));
+    assert_airdrop_error(send(&mut env.svm, &[&users[2]], &[ix]), AirdropError::InvalidProof);
+    let ix = env.ix_merkle_claim(&users[2].pubkey(), 2, list[2].1, &merkle_proof(&levels, 2));
+    send(&mut env.svm, &[&users[2]], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&users[2].pubkey())), list[2].1);
+    assert_eq!(env.svm.get_account(&env.distributor()).unwrap().data[40..], [0b101]);
+}
+
+#[test]
+fn test_airdrop_cooldown_and_cap() {
+    let mut env = setup();
+    // 两次领取至少间隔 100 个 slot，每个钱包累计最多领取两次
+    let ix = env.ix_config(AMOUNT, 100, AMOUNT * 2, false);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let user = env.user();
+    let mana_spla = env.ata(&env.mana_auth());
+
+    let slot = env.svm.get_sysvar::<Clock>().slot;
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+
+    // 冷却时间内不能再次领取
+    env.svm.warp_to_slot(slot + 99);
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    assert_airdrop_error(send(&mut env.svm, &[&user], &[ix]), AirdropError::ClaimCooldown);
+
+    // 冷却时间结束后可以再次领取
+    env.svm.warp_to_slot(slot + 100);
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT * 2);
+
+    // 冷却时间已过，但累计数量会超过上限
+    env.svm.warp_to_slot(slot + 200);
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    assert_airdrop_error(send(&mut env.svm, &[&user], &[ix]), AirdropError::ClaimCapExceeded);
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT * 2);
+}
+
+#[test]
+fn test_airdrop_legacy_token() {
+    let mut env = setup_mint(spl_token::id(), None);
+    let ix = env.ix_config(AMOUNT, 0, 0, false);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let user = env.user();
+    let mana_spla = env.ata(&env.mana_auth());
+
+    // 代币程序与铸造账户所属程序不一致时拒绝
+    let mut ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    ix.accounts[7] = AccountMeta::new_readonly(spl_token_2022::id(), false);
+    assert_airdrop_error(send(&mut env.svm, &[&user], &[ix]), AirdropError::WrongTokenProgram);
+
+    // 用户的关联代币账户由 SPL Token 程序创建，SPL Token 没有转账手续费，到账数量等于空投数量
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    let meta = send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(meta.return_data.data, AMOUNT.to_be_bytes());
+    let user_spla = env.ata(&user.pubkey());
+    assert_eq!(env.svm.get_account(&user_spla).unwrap().owner, spl_token::id());
+    assert_eq!(env.balance(&user_spla), AMOUNT);
+    assert_eq!(env.balance(&mana_spla), SUPPLY - AMOUNT);
+}
+
+#[test]
+fn test_airdrop_transfer_fee() {
+    // 转账手续费 1%，最高 1 个代币
+    let maximum_fee = 1_000_000_000;
+    let mut env = setup_mint(spl_token_2022::id(), Some((100, maximum_fee)));
+    let ix = env.ix_config(AMOUNT, 0, 0, false);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let user = env.user();
+    let mana_spla = env.ata(&env.mana_auth());
+
+    // 手续费由接收方承担，返回数据为扣除手续费后的到账数量
+    let fee = AMOUNT / 100;
+    let net = AMOUNT - fee;
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    let meta = send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(meta.return_data.data, net.to_be_bytes());
+    assert!(meta.logs.iter().any(|log| log.ends_with(&format!("received {net} after transfer fee"))));
+
+    // 用户到账 net，手续费暂扣在用户的代币账户中，Mana 的代币账户扣除完整的空投数量
+    let user_spla = env.ata(&user.pubkey());
+    assert_eq!(env.balance(&user_spla), net);
+    let account = env.svm.get_account(&user_spla).unwrap();
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).unwrap();
+    assert_eq!(u64::from(state.get_extension::<TransferFeeAmount>().unwrap().withheld_amount), fee);
+    assert_eq!(env.balance(&mana_spla), SUPPLY - AMOUNT);
+}
+
+#[test]
+fn test_treasury_refill_and_close() {
+    let mut env = setup();
+    let mana_spla = env.ata(&env.mana_auth());
+
+    // 任何人都可以补充国库
+    let funder = env.user();
+    let ixs = [
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &funder.pubkey(),
+            &funder.pubkey(),
+            &env.mint,
+            &env.spl,
+        ),
+        spl_token_2022::instruction::mint_to(
+            &env.spl,
+            &env.mint,
+            &env.ata(&funder.pubkey()),
+            &env.admin.pubkey(),
+            &[],
+            AMOUNT * 2,
+        )
+        .unwrap(),
+    ];
+    send(&mut env.svm, &[&funder, &env.admin], &ixs).unwrap();
+    let ix = env.ix_refill(&funder.pubkey(), AMOUNT);
+    send(&mut env.svm, &[&funder], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&funder.pubkey())), AMOUNT);
+    assert_eq!(env.balance(&mana_spla), SUPPLY + AMOUNT);
+
+    let into = env.ata(&env.admin.pubkey());
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &env.admin.pubkey(),
+        &env.admin.pubkey(),
+        &env.mint,
+        &env.spl,
+    );
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+
+    // 非管理员不能关闭国库
+    let attacker = env.user();
+    let mut ix = env.ix_treasury(into, vec![0x05]);
+    ix.accounts[0] = AccountMeta::new(attacker.pubkey(), true);
+    assert_airdrop_error(send(&mut env.svm, &[&attacker], &[ix]), AirdropError::Unauthorized);
+
+    // 管理员关闭国库：剩余代币全部转出，Mana 的关联代币账户和配置账户关闭，租金退还给管理员
+    // 由出资者支付手续费，便于核对管理员收到的租金
+    let rent = env.svm.get_account(&mana_spla).unwrap().lamports + env.svm.get_account(&env.config()).unwrap().lamports;
+    let lamports = env.svm.get_account(&env.admin.pubkey()).unwrap().lamports;
+    let ix = env.ix_treasury(into, vec![0x05]);
+    send(&mut env.svm, &[&funder, &env.admin], &[ix]).unwrap();
+    assert_eq!(env.balance(&into), SUPPLY + AMOUNT);
+    assert!(env.svm.get_account(&mana_spla).is_none_or(|account| account.lamports == 0));
+    assert!(env.svm.get_account(&env.config()).is_none_or(|account| account.lamports == 0));
+    assert_eq!(env.svm.get_account(&env.admin.pubkey()).unwrap().lamports, lamports + rent);
+
+    // 关闭后不能再空投
+    let user = env.user();
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    assert_airdrop_error(send(&mut env.svm, &[&user], &[ix]), AirdropError::WrongConfig);
+
+    // 补充国库时重新创建 Mana 的关联代币账户，重新配置后可以继续空投
+    let ix = env.ix_refill(&funder.pubkey(), AMOUNT);
+    send(&mut env.svm, &[&funder], &[ix]).unwrap();
+    assert_eq!(env.balance(&mana_spla), AMOUNT);
+    let ix = env.ix_config(AMOUNT, 0, 0, false);
+    send(&mut env.svm, &[&env.admin], &[ix]).unwrap();
+    let ix = env.ix_airdrop(&user.pubkey(), mana_spla);
+    send(&mut env.svm, &[&user], &[ix]).unwrap();
+    assert_eq!(env.balance(&env.ata(&user.pubkey())), AMOUNT);
+}